@@ -4,13 +4,18 @@ import os
 import glob
 import ast
 dirname = os.path.dirname(__file__)
-solution = glob.glob("solution.txt")
+solution = glob.glob("solution.json") or glob.glob("solution.txt")
 print(solution)
 
 if solution:
     with open(solution[0]) as solution:
-        train_file = solution.readline().strip()
-        solution_list = ast.literal_eval(solution.readline())
+        if solution.name.endswith(".json"):
+            solution_json = json.load(solution)
+            train_file = solution_json["instance_name"]
+            solution_list = [[visit["patient"] for visit in route["visits"]] for route in solution_json["routes"]]
+        else:
+            train_file = solution.readline().strip()
+            solution_list = ast.literal_eval(solution.readline())
     print(train_file + ".json")
     data = glob.glob("train\\" + train_file + ".json")
     print(data)
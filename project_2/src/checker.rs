@@ -0,0 +1,357 @@
+//! Feasibility checker for solutions from any source.
+//! Deliberately shares no code with the fitness evaluation, so it can be used to audit it.
+
+use std::collections::HashMap;
+use std::fmt;
+use crate::parsing::{BreakRule, Nurse, TimeWindowKind, TrainData};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LateVisit {
+    pub patient: u16,
+    pub finish: f64,
+    pub end_time: f64,
+}
+
+/// A synchronized pair starting `gap` minutes apart or with one nurse,
+/// or a precedence pair with `then` starting `gap` minutes after `first`, outside the allowed gaps
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmetPair {
+    pub first: u16,
+    pub then: u16,
+    pub gap: f64,
+    pub synchronized: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NurseReport {
+    pub nurse: usize,
+    pub travel_time: f64,
+    pub load: i32,
+    pub overload: i32,
+    pub return_time: f64,
+    /// Time the nurse is back after the end of the shift, 0 if on time
+    pub depot_overtime: f64,
+    /// Visits finishing after a hard window's end_time
+    pub late_visits: Vec<LateVisit>,
+    /// Patients needing a skill the nurse does not have
+    pub unqualified_visits: Vec<u16>,
+    /// Earliness and tardiness cost of soft windows, not a violation
+    pub window_cost: f64,
+    /// Start of the nurse's break, None without one
+    pub break_start: Option<f64>,
+    /// Time the break starts after the latest start its window allows
+    pub late_break: f64,
+    /// Time worked beyond the maximum working time without a break
+    pub overwork: f64,
+}
+
+impl NurseReport {
+    pub fn is_feasible(&self) -> bool {
+        self.problems() == 0
+    }
+
+    fn problems(&self) -> usize {
+        self.late_visits.len() + self.unqualified_visits.len()
+            + (self.overload > 0) as usize + (self.depot_overtime > 0.0) as usize + (self.late_break > 0.0) as usize + (self.overwork > 0.0) as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeasibilityReport {
+    pub nurses: Vec<NurseReport>,
+    pub total_travel_time: f64,
+    pub total_window_cost: f64,
+    pub unmet_pairs: Vec<UnmetPair>,
+    /// Routes beyond the instance's number of nurses
+    pub extra_routes: usize,
+    /// Unvisited patients without a drop cost
+    pub missing_patients: Vec<u16>,
+    /// Unvisited patients with a drop cost, not a violation
+    pub dropped_patients: Vec<u16>,
+    pub total_drop_cost: f64,
+    pub duplicate_patients: Vec<u16>,
+    pub unknown_patients: Vec<u16>,
+}
+
+impl FeasibilityReport {
+    pub fn is_feasible(&self) -> bool {
+        self.is_complete() && self.unmet_pairs.is_empty() && self.nurses.iter().all(NurseReport::is_feasible)
+    }
+
+    /// Every patient without a drop cost is visited, and no patient more than once, by one of the instance's nurses
+    pub fn is_complete(&self) -> bool {
+        self.extra_routes == 0
+            && self.missing_patients.is_empty()
+            && self.duplicate_patients.is_empty()
+            && self.unknown_patients.is_empty()
+    }
+}
+
+/// Rounds of checking all routes again while synchronized and precedence-constrained visits wait for each other
+const TIMING_ROUNDS: usize = 16;
+
+/// Recomputes travel time, time windows, load and return time of every route against the instance and its nurses.
+/// Nurses leave their start location at shift start and wait at a patient until its start_time, except at soft-both windows.
+/// Travel takes as long as the speed profile says at the time the nurse leaves.
+/// Only hard windows can be violated, soft windows cost their earliness and tardiness.
+/// Every skill a patient lists must be among the skills of the nurse visiting it.
+/// Synchronized visits wait for each other and precedence pairs wait for the minimum gap, pairs still off are violations.
+/// Patients with a drop cost may be left out of the routes at that cost.
+/// A nurse still out when its break window opens takes the break after whichever visit breaks the fewest constraints.
+pub fn check_routes(routes: &[Vec<u16>], t_data: &TrainData) -> FeasibilityReport {
+    let num_patients = t_data.nbr_patients();
+    let mut visits = vec![0usize; num_patients + 1];
+    let mut unknown_patients: Vec<u16> = Vec::new();
+    for &patient_id in routes.iter().flatten() {
+        if patient_id != 0 && t_data.patients.contains_key(&patient_id.to_string()) {
+            visits[patient_id as usize] += 1;
+        } else {
+            unknown_patients.push(patient_id);
+        }
+    }
+
+    let mut wait_for: HashMap<u16, f64> = HashMap::new();
+    let mut starts: HashMap<u16, (f64, usize)> = HashMap::new();
+    let mut nurses: Vec<NurseReport> = Vec::new();
+    for _ in 0..=TIMING_ROUNDS {
+        starts.clear();
+        nurses = routes.iter().enumerate().map(|(nurse, route)| check_nurse(nurse, route, t_data, &wait_for, &mut starts)).collect();
+
+        let mut waits: Vec<(u16, f64)> = Vec::new();
+        for pair in t_data.synchronized.iter() {
+            if let (Some(a), Some(b)) = (starts.get(&pair[0]), starts.get(&pair[1])) {
+                if a.0 < b.0 { waits.push((pair[0], b.0)) } else if b.0 < a.0 { waits.push((pair[1], a.0)) }
+            }
+        }
+        for p in t_data.precedences.iter() {
+            if let (Some(first), Some(then)) = (starts.get(&p.first), starts.get(&p.then)) {
+                if then.0 - first.0 < p.min_gap {
+                    waits.push((p.then, first.0 + p.min_gap));
+                }
+                if let Some(max_gap) = p.max_gap.filter(|&max_gap| then.0 - first.0 > max_gap) {
+                    waits.push((p.first, then.0 - max_gap));
+                }
+            }
+        }
+        waits.retain(|(patient, time)| *time > starts[patient].0 + 1e-6);
+        if waits.is_empty() {
+            break;
+        }
+        wait_for.extend(waits);
+    }
+
+    let mut unmet_pairs: Vec<UnmetPair> = Vec::new();
+    for pair in t_data.synchronized.iter() {
+        if let (Some(a), Some(b)) = (starts.get(&pair[0]), starts.get(&pair[1])) {
+            if (a.0 - b.0).abs() > 1e-6 || a.1 == b.1 {
+                unmet_pairs.push(UnmetPair { first: pair[0], then: pair[1], gap: b.0 - a.0, synchronized: true });
+            }
+        }
+    }
+    for p in t_data.precedences.iter() {
+        if let (Some(first), Some(then)) = (starts.get(&p.first), starts.get(&p.then)) {
+            let gap = then.0 - first.0;
+            if gap < p.min_gap - 1e-6 || p.max_gap.is_some_and(|max_gap| gap > max_gap + 1e-6) {
+                unmet_pairs.push(UnmetPair { first: p.first, then: p.then, gap, synchronized: false });
+            }
+        }
+    }
+
+    let (dropped_patients, missing_patients): (Vec<u16>, Vec<u16>) = (1..=num_patients as u16)
+        .filter(|&p| visits[p as usize] == 0)
+        .partition(|p| drop_cost(*p, t_data).is_some());
+
+    FeasibilityReport {
+        total_travel_time: nurses.iter().map(|n| n.travel_time).sum(),
+        total_window_cost: nurses.iter().map(|n| n.window_cost).sum(),
+        nurses,
+        unmet_pairs,
+        extra_routes: routes.len().saturating_sub(t_data.nbr_nurses as usize),
+        total_drop_cost: dropped_patients.iter().filter_map(|&p| drop_cost(p, t_data)).sum(),
+        dropped_patients,
+        missing_patients,
+        duplicate_patients: (1..=num_patients).filter(|&p| visits[p] > 1).map(|p| p as u16).collect(),
+        unknown_patients,
+    }
+}
+
+fn drop_cost(patient_id: u16, t_data: &TrainData) -> Option<f64> {
+    t_data.patients.get(&patient_id.to_string()).and_then(|p| p.drop_cost)
+}
+
+/// Routes beyond the fleet are already reported, check them as if driven by the last nurse
+fn shift(nurse: usize, t_data: &TrainData) -> Option<&Nurse> {
+    t_data.nurses.as_ref().map(|nurses| &nurses[nurse.min(nurses.len() - 1)])
+}
+
+fn break_rule(nurse: usize, t_data: &TrainData) -> Option<BreakRule> {
+    shift(nurse, t_data).and_then(|n| n.break_rule).or(t_data.break_rule)
+}
+
+/// Checks one route with the break, if the nurse needs one, at the position breaking the fewest constraints
+fn check_nurse(nurse: usize, route: &[u16], t_data: &TrainData, wait_for: &HashMap<u16, f64>, starts: &mut HashMap<u16, (f64, usize)>) -> NurseReport {
+    let mut scratch: HashMap<u16, (f64, usize)> = HashMap::new();
+    let without = check_route(nurse, route, t_data, wait_for, &mut scratch, None);
+    let shift_start = shift(nurse, t_data).map_or(0.0, |n| n.shift_start);
+    let needs_break = break_rule(nurse, t_data).is_some_and(|rule| !route.is_empty() && without.return_time > rule.start && shift_start < rule.end);
+    if !needs_break {
+        return check_route(nurse, route, t_data, wait_for, starts, None);
+    }
+    let badness = |r: &NurseReport| (
+        r.problems(),
+        r.late_visits.iter().map(|l| l.finish - l.end_time).sum::<f64>() + r.depot_overtime + r.late_break + r.overwork + r.window_cost,
+        r.return_time,
+    );
+    let mut best: Option<(usize, (usize, f64, f64))> = None;
+    for after in 0..=route.len() {
+        let key = badness(&check_route(nurse, route, t_data, wait_for, &mut scratch, Some(after)));
+        if best.is_none_or(|(_, b)| key.partial_cmp(&b) == Some(std::cmp::Ordering::Less)) {
+            best = Some((after, key));
+        }
+    }
+    check_route(nurse, route, t_data, wait_for, starts, best.map(|(after, _)| after))
+}
+
+/// Checks one route, visits start no earlier than `wait_for` says. Records the start and route of every visit in `starts`.
+/// The nurse's break is taken after the first `rest_after` visits, starting no earlier than its window
+fn check_route(nurse: usize, route: &[u16], t_data: &TrainData, wait_for: &HashMap<u16, f64>, starts: &mut HashMap<u16, (f64, usize)>, rest_after: Option<usize>) -> NurseReport {
+    let mut report = NurseReport {
+        nurse,
+        travel_time: 0.0,
+        load: 0,
+        overload: 0,
+        return_time: 0.0,
+        depot_overtime: 0.0,
+        late_visits: Vec::new(),
+        unqualified_visits: Vec::new(),
+        window_cost: 0.0,
+        break_start: None,
+        late_break: 0.0,
+        overwork: 0.0,
+    };
+    let shift = shift(nurse, t_data);
+    let rule = break_rule(nurse, t_data);
+    let capacity = shift.map_or(t_data.capacity_nurse, |n| n.capacity);
+    let (start_location, end_location) = shift.map_or((0, 0), |n| (n.start_location, n.end_location));
+    let shift_end = shift.map_or(t_data.depot.return_time, |n| n.shift_end);
+    let skills: &[String] = shift.map_or(&[], |n| &n.skills);
+    let mut time = shift.map_or(0.0, |n| n.shift_start);
+    let mut location: usize = start_location;
+    let mut visited = false;
+    let rest = |time: &mut f64, report: &mut NurseReport| if let Some(rule) = rule {
+        let start = time.max(rule.start);
+        report.break_start = Some(start);
+        report.late_break = (start + rule.duration - rule.end).max(0.0);
+        *time = start + rule.duration;
+    };
+    for (index, &patient_id) in route.iter().enumerate() {
+        if rest_after == Some(index) {
+            rest(&mut time, &mut report);
+        }
+        let patient = match t_data.patients.get(&patient_id.to_string()) {
+            Some(p) if patient_id != 0 => p,
+            _ => continue,
+        };
+
+        let travel = t_data.travel_time(location, patient_id as usize, time);
+        report.travel_time += travel;
+        let mut start = match patient.time_window {
+            TimeWindowKind::SoftBoth => time + travel,
+            _ => (time + travel).max(patient.start_time),
+        };
+        if let Some(&wait) = wait_for.get(&patient_id) {
+            start = start.max(wait);
+        }
+        starts.insert(patient_id, (start, nurse));
+        time = start + patient.care_time;
+        match patient.time_window {
+            TimeWindowKind::Hard if time > patient.end_time =>
+                report.late_visits.push(LateVisit { patient: patient_id, finish: time, end_time: patient.end_time }),
+            TimeWindowKind::Hard => {}
+            _ => {
+                report.window_cost += patient.earliness_cost * (patient.start_time - start).max(0.0)
+                    + patient.tardiness_cost * (time - patient.end_time).max(0.0);
+            }
+        }
+        if !patient.skills.iter().all(|skill| skills.contains(skill)) {
+            report.unqualified_visits.push(patient_id);
+        }
+        report.load += patient.demand;
+        location = patient_id as usize;
+        visited = true;
+    }
+    if rest_after == Some(route.len()) {
+        rest(&mut time, &mut report);
+    }
+    if visited {
+        let travel = t_data.travel_time(location, end_location, time);
+        report.travel_time += travel;
+        time += travel;
+    }
+    report.return_time = time;
+    let shift_start = shift.map_or(0.0, |n| n.shift_start);
+    if let Some(limit) = shift.and_then(|n| n.max_working_time).or(t_data.max_working_time) {
+        report.overwork = match (report.break_start, rule) {
+            (Some(start), Some(rule)) => (start - shift_start - limit).max(0.0) + (time - start - rule.duration - limit).max(0.0),
+            _ => (time - shift_start - limit).max(0.0),
+        };
+    }
+    report.depot_overtime = (time - shift_end).max(0.0);
+    report.overload = (report.load - capacity).max(0);
+    report
+}
+
+impl fmt::Display for FeasibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total travel time: {:.2}", self.total_travel_time)?;
+        if self.total_window_cost > 0.0 {
+            writeln!(f, "Soft time window cost: {:.2}", self.total_window_cost)?;
+        }
+        writeln!(f, "Feasible: {}", self.is_feasible())?;
+        for nurse in self.nurses.iter().filter(|n| !n.is_feasible()) {
+            write!(f, "Nurse {:0>2}\tload {}\treturn {:.2}", nurse.nurse, nurse.load, nurse.return_time)?;
+            if nurse.overload > 0 {
+                write!(f, "\toverloaded by {}", nurse.overload)?;
+            }
+            if nurse.depot_overtime > 0.0 {
+                write!(f, "\tback {:.2} late", nurse.depot_overtime)?;
+            }
+            for late in nurse.late_visits.iter() {
+                write!(f, "\tpatient {} done {:.2} > {}", late.patient, late.finish, late.end_time)?;
+            }
+            for patient in nurse.unqualified_visits.iter() {
+                write!(f, "\tpatient {} needs skills the nurse lacks", patient)?;
+            }
+            if nurse.late_break > 0.0 {
+                write!(f, "\tbreak {:.2} late", nurse.late_break)?;
+            }
+            if nurse.overwork > 0.0 {
+                write!(f, "\tworks {:.2} too long without a break", nurse.overwork)?;
+            }
+            writeln!(f)?;
+        }
+        for pair in self.unmet_pairs.iter() {
+            if pair.synchronized {
+                writeln!(f, "Patients {} and {} are not visited together, {:.2} apart", pair.first, pair.then, pair.gap)?;
+            } else {
+                writeln!(f, "Patient {} is visited {:.2} after patient {}, outside the allowed gap", pair.then, pair.gap, pair.first)?;
+            }
+        }
+        if self.extra_routes > 0 {
+            writeln!(f, "{} routes more than nurses", self.extra_routes)?;
+        }
+        if !self.dropped_patients.is_empty() {
+            writeln!(f, "Unassigned patients: {:?} (drop cost {:.2})", self.dropped_patients, self.total_drop_cost)?;
+        }
+        if !self.missing_patients.is_empty() {
+            writeln!(f, "Missing patients: {:?}", self.missing_patients)?;
+        }
+        if !self.duplicate_patients.is_empty() {
+            writeln!(f, "Patients visited more than once: {:?}", self.duplicate_patients)?;
+        }
+        if !self.unknown_patients.is_empty() {
+            writeln!(f, "Unknown patients: {:?}", self.unknown_patients)?;
+        }
+        Ok(())
+    }
+}
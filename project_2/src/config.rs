@@ -0,0 +1,265 @@
+use std::fs;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::error::ConfigError;
+
+/// What the islands minimize, see [`ObjectiveFitness`](crate::fitness::ObjectiveFitness)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// Travel time with weighted penalties for broken constraints
+    #[default]
+    Penalty,
+    /// Travel time only
+    Distance,
+    /// Fewest broken constraints first, then travel time
+    Lexicographic,
+}
+
+impl FromStr for Objective {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "penalty" => Ok(Objective::Penalty),
+            "distance" => Ok(Objective::Distance),
+            "lexicographic" => Ok(Objective::Lexicographic),
+            _ => Err(ConfigError::OutOfRange { field: "objective", reason: format!("{}, must be penalty, distance or lexicographic", s) }),
+        }
+    }
+}
+
+/// Hyperparameters of the GA and its islands, loaded from a TOML or JSON file where every field may be left out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GaConfig {
+    /// Population standard deviation of the fitness at and above which the mutation probability is `p_mut_min`.
+    /// Every unit below adds 0.1 to it
+    pub min_pop_dev: f64,
+    pub p_mut_min: f64,
+    /// Chance offspring is a crossover of its parents rather than a copy of one
+    pub xover_prob: f64,
+    /// Offspring per individual each generation, before survivor selection
+    pub pop_size_multiplier: usize,
+    /// Individuals per island
+    pub pop_size: usize,
+    /// Generations every island evolves, 100 unless configured
+    pub generations: usize,
+    /// Islands, each evolved in its own thread
+    pub islands: usize,
+    /// Generations between sending individuals to the next island
+    pub send_interval: usize,
+    /// Individuals sent each time
+    pub migrants: usize,
+    /// Generations between checks whether the average fitness stagnated, which restarts all but the two best
+    pub stagnation_interval: usize,
+    /// Individuals in each of the two tournaments picking parents
+    pub tournament_size: usize,
+    /// Individuals of a `--reoptimize` run, the previous plan is one of them
+    pub reoptimize_pop_size: usize,
+    pub reoptimize_generations: usize,
+    /// Improvement steps for the plan of a multi-day instance
+    pub week_iterations: usize,
+    /// Cost per patient visited by another nurse than in the `--previous` solution
+    pub continuity_cost: f64,
+    pub objective: Objective,
+    /// Lets every island adapt the weights of the penalty objective, see [`AdaptivePenalty`](crate::fitness::AdaptivePenalty)
+    pub adaptive_penalty: bool,
+}
+
+impl Default for GaConfig {
+    fn default() -> Self {
+        Self {
+            min_pop_dev: 4.0,
+            p_mut_min: 0.07,
+            xover_prob: 0.97,
+            pop_size_multiplier: 5,
+            pop_size: 300,
+            generations: 100,
+            islands: 8,
+            send_interval: 150,
+            migrants: 5,
+            stagnation_interval: 10,
+            tournament_size: 2,
+            reoptimize_pop_size: 100,
+            reoptimize_generations: 300,
+            week_iterations: 200_000,
+            continuity_cost: 10.0,
+            objective: Objective::Penalty,
+            adaptive_penalty: false,
+        }
+    }
+}
+
+impl GaConfig {
+    pub fn builder() -> GaConfigBuilder {
+        GaConfig::default().into_builder()
+    }
+
+    /// Builder starting from this config, e.g. to override some fields of a loaded file
+    pub fn into_builder(self) -> GaConfigBuilder {
+        GaConfigBuilder { config: self }
+    }
+
+    /// Reads a `.toml` or `.json` config file
+    pub fn load(filepath: &str) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(filepath).map_err(|source| ConfigError::Read { path: filepath.to_string(), source })?;
+        let config: GaConfig = if filepath.ends_with(".toml") {
+            toml::from_str(&contents)?
+        } else if filepath.ends_with(".json") {
+            serde_json::from_str(&contents)?
+        } else {
+            return Err(ConfigError::UnknownFormat { path: filepath.to_string() });
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Mutation probability of a population with fitness standard deviation `std`
+    pub fn mutation_probability(&self, std: f64) -> f64 {
+        self.p_mut_min + 0.1 * (self.min_pop_dev - std.min(self.min_pop_dev))
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let out_of_range = |field, reason: String| Err(ConfigError::OutOfRange { field, reason });
+        if !(self.min_pop_dev >= 0.0 && self.min_pop_dev.is_finite()) {
+            return out_of_range("min_pop_dev", format!("{}, must be a number >= 0", self.min_pop_dev));
+        }
+        if !(0.0..=1.0).contains(&self.p_mut_min) || self.mutation_probability(0.0) > 1.0 {
+            return out_of_range("p_mut_min", format!("{} with min_pop_dev {} gives mutation probabilities up to {}, must be in [0, 1]",
+                                                     self.p_mut_min, self.min_pop_dev, self.mutation_probability(0.0)));
+        }
+        if !(0.0..=1.0).contains(&self.xover_prob) {
+            return out_of_range("xover_prob", format!("{}, must be in [0, 1]", self.xover_prob));
+        }
+        if !(self.continuity_cost >= 0.0 && self.continuity_cost.is_finite()) {
+            return out_of_range("continuity_cost", format!("{}, must be a number >= 0", self.continuity_cost));
+        }
+        // Each parent is the best of its own tournament, and stagnation keeps the two best
+        let min_pop_size = 2 * self.tournament_size.max(1);
+        if self.pop_size < min_pop_size {
+            return out_of_range("pop_size", format!("{} with tournament_size {}, need at least {}", self.pop_size, self.tournament_size, min_pop_size));
+        }
+        if self.reoptimize_pop_size < min_pop_size {
+            return out_of_range("reoptimize_pop_size", format!("{} with tournament_size {}, need at least {}", self.reoptimize_pop_size, self.tournament_size, min_pop_size));
+        }
+        if self.migrants > self.pop_size {
+            return out_of_range("migrants", format!("{} from a population of {}", self.migrants, self.pop_size));
+        }
+        if self.adaptive_penalty && self.objective != Objective::Penalty {
+            return out_of_range("adaptive_penalty", format!("the {:?} objective has no penalty weights", self.objective));
+        }
+        let counts = [
+            ("pop_size_multiplier", self.pop_size_multiplier),
+            ("generations", self.generations),
+            ("islands", self.islands),
+            ("send_interval", self.send_interval),
+            ("stagnation_interval", self.stagnation_interval),
+            ("tournament_size", self.tournament_size),
+            ("reoptimize_generations", self.reoptimize_generations),
+            ("week_iterations", self.week_iterations),
+        ];
+        if let Some((field, _)) = counts.iter().find(|(_, count)| *count == 0) {
+            return out_of_range(field, "0, need at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Builds a validated [`GaConfig`] field by field
+#[derive(Debug, Clone)]
+pub struct GaConfigBuilder {
+    config: GaConfig,
+}
+
+impl GaConfigBuilder {
+    pub fn min_pop_dev(mut self, min_pop_dev: f64) -> Self {
+        self.config.min_pop_dev = min_pop_dev;
+        self
+    }
+
+    pub fn p_mut_min(mut self, p_mut_min: f64) -> Self {
+        self.config.p_mut_min = p_mut_min;
+        self
+    }
+
+    pub fn xover_prob(mut self, xover_prob: f64) -> Self {
+        self.config.xover_prob = xover_prob;
+        self
+    }
+
+    pub fn pop_size_multiplier(mut self, pop_size_multiplier: usize) -> Self {
+        self.config.pop_size_multiplier = pop_size_multiplier;
+        self
+    }
+
+    pub fn pop_size(mut self, pop_size: usize) -> Self {
+        self.config.pop_size = pop_size;
+        self
+    }
+
+    pub fn generations(mut self, generations: usize) -> Self {
+        self.config.generations = generations;
+        self
+    }
+
+    pub fn islands(mut self, islands: usize) -> Self {
+        self.config.islands = islands;
+        self
+    }
+
+    pub fn send_interval(mut self, send_interval: usize) -> Self {
+        self.config.send_interval = send_interval;
+        self
+    }
+
+    pub fn migrants(mut self, migrants: usize) -> Self {
+        self.config.migrants = migrants;
+        self
+    }
+
+    pub fn stagnation_interval(mut self, stagnation_interval: usize) -> Self {
+        self.config.stagnation_interval = stagnation_interval;
+        self
+    }
+
+    pub fn tournament_size(mut self, tournament_size: usize) -> Self {
+        self.config.tournament_size = tournament_size;
+        self
+    }
+
+    pub fn reoptimize_pop_size(mut self, reoptimize_pop_size: usize) -> Self {
+        self.config.reoptimize_pop_size = reoptimize_pop_size;
+        self
+    }
+
+    pub fn reoptimize_generations(mut self, reoptimize_generations: usize) -> Self {
+        self.config.reoptimize_generations = reoptimize_generations;
+        self
+    }
+
+    pub fn week_iterations(mut self, week_iterations: usize) -> Self {
+        self.config.week_iterations = week_iterations;
+        self
+    }
+
+    pub fn continuity_cost(mut self, continuity_cost: f64) -> Self {
+        self.config.continuity_cost = continuity_cost;
+        self
+    }
+
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.config.objective = objective;
+        self
+    }
+
+    pub fn adaptive_penalty(mut self, adaptive_penalty: bool) -> Self {
+        self.config.adaptive_penalty = adaptive_penalty;
+        self
+    }
+
+    pub fn build(self) -> Result<GaConfig, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
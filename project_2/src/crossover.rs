@@ -139,7 +139,7 @@ impl Crossover for HeuristicCrossover{
         let mut p1 = parent_a.clone();
         let mut p2 = parent_b.clone();
         let cut_point:usize  = rng.gen_range(0..parent_a.len());
-        let (slice_1, slice_2) = if cut_point > (p1.len() / 2) as usize {(cut_point..p1.len() - 1, 0..cut_point)} else {(0..cut_point, cut_point..p1.len() - 1)};
+        let (slice_1, slice_2) = if cut_point > p1.len() / 2 {(cut_point..p1.len() - 1, 0..cut_point)} else {(0..cut_point, cut_point..p1.len() - 1)};
         child.push(p1[slice_1.start]);
 
         let pos = p2.iter().position(|&a| a == p1[slice_1.start]).unwrap();
@@ -197,7 +197,7 @@ impl Crossover for MergeCrossover{
         let mut p1 = parent_a.clone();
         let mut p2 = parent_b.clone();
         let cut_point:usize  = rng.gen_range(0..parent_a.len());
-        let (slice_1, slice_2) = if cut_point > (p1.len() / 2) as usize {(cut_point..p1.len() - 1, 0..cut_point)} else {(0..cut_point, cut_point..p1.len() - 1)};
+        let (slice_1, slice_2) = if cut_point > p1.len() / 2 {(cut_point..p1.len() - 1, 0..cut_point)} else {(0..cut_point, cut_point..p1.len() - 1)};
         child.push(p1[slice_1.start]);
 
         let pos = p2.iter().position(|&a| a == p1[slice_1.start]).unwrap();
@@ -205,8 +205,8 @@ impl Crossover for MergeCrossover{
 
         for i in slice_1.start..slice_1.end
         {
-            let dist_1: f64 = t_data.patients[&p1[i].to_string()].end_time - t_data.patients[&p1[i].to_string()].care_time;
-            let dist_2: f64 = t_data.patients[&p2[i].to_string()].end_time - t_data.patients[&p2[i].to_string()].care_time;
+            let dist_1: f64 = t_data.patient(p1[i]).end_time - t_data.patient(p1[i]).care_time;
+            let dist_2: f64 = t_data.patient(p2[i]).end_time - t_data.patient(p2[i]).care_time;
 
 
 
@@ -0,0 +1,125 @@
+use std::fmt;
+
+/// Errors from reading an instance file, pointing at the field, patient or matrix row that is wrong
+#[derive(Debug)]
+pub enum ParseError {
+    Read { path: String, source: std::io::Error },
+    Json(serde_json::Error),
+    UnknownFormat { path: String },
+    MissingSection(&'static str),
+    InvalidRow { line: usize, content: String },
+    InvalidPatientId { id: String },
+    MissingPatient { id: usize },
+    MatrixRows { expected: usize, found: usize },
+    NonSquareMatrix { row: usize, expected: usize, found: usize },
+    NegativeValue { patient: String, field: &'static str, value: f64 },
+    InvalidField { field: &'static str, reason: String },
+}
+
+/// Errors from reading a GA configuration file or building a [`GaConfig`](crate::config::GaConfig)
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: String, source: std::io::Error },
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    UnknownFormat { path: String },
+    OutOfRange { field: &'static str, reason: String },
+}
+
+/// Errors from writing solutions to disk or handing them to an external tool
+#[derive(Debug)]
+pub enum IoError {
+    Write { path: String, source: std::io::Error },
+    Serialize(serde_json::Error),
+    /// An external command failed to start or exited unsuccessfully
+    Command { command: String, source: std::io::Error },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Read { path, source } => write!(f, "unable to read {}: {}", path, source),
+            ParseError::Json(e) => write!(f, "invalid instance json: {}", e),
+            ParseError::UnknownFormat { path } => write!(f, "unknown instance format: {}", path),
+            ParseError::MissingSection(section) => write!(f, "missing {} section", section),
+            ParseError::InvalidRow { line, content } => write!(f, "invalid row at line {}: '{}'", line, content),
+            ParseError::InvalidPatientId { id } => write!(f, "patient id '{}' is not a positive integer", id),
+            ParseError::MissingPatient { id } => write!(f, "patient ids have a gap, patient {} is missing", id),
+            ParseError::MatrixRows { expected, found } => write!(f, "travel_times has {} rows, expected {}", found, expected),
+            ParseError::NonSquareMatrix { row, expected, found } => write!(f, "travel_times row {} has {} columns, expected {}", row, found, expected),
+            ParseError::NegativeValue { patient, field, value } => write!(f, "patient {} has negative {}: {}", patient, field, value),
+            ParseError::InvalidField { field, reason } => write!(f, "invalid {}: {}", field, reason),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => write!(f, "unable to read {}: {}", path, source),
+            ConfigError::Toml(e) => write!(f, "invalid config toml: {}", e),
+            ConfigError::Json(e) => write!(f, "invalid config json: {}", e),
+            ConfigError::UnknownFormat { path } => write!(f, "config {} is neither .toml nor .json", path),
+            ConfigError::OutOfRange { field, reason } => write!(f, "invalid {}: {}", field, reason),
+        }
+    }
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoError::Write { path, source } => write!(f, "unable to write {}: {}", path, source),
+            IoError::Serialize(e) => write!(f, "unable to serialize solution: {}", e),
+            IoError::Command { command, source } => write!(f, "unable to run {}: {}", command, source),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Read { source, .. } => Some(source),
+            ParseError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            ConfigError::Toml(e) => Some(e),
+            ConfigError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for IoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IoError::Write { source, .. } => Some(source),
+            IoError::Serialize(e) => Some(e),
+            IoError::Command { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::Json(e)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        ConfigError::Toml(e)
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self {
+        ConfigError::Json(e)
+    }
+}
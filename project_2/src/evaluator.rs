@@ -0,0 +1,585 @@
+use serde::{Deserialize, Serialize};
+use crate::helper;
+use crate::individual::chromosome::Chromosome;
+use crate::mutation::Touched;
+use crate::parsing::{Nurse, TrainData};
+
+/// Weights turning route statistics into a penalized fitness:
+/// `travel * (1 + break_factor * breaks) + lateness * minutes late + overload * excess load + depot_return * minutes overtime +
+/// timing * minutes off at synchronized and precedence pairs`.
+/// Soft time window, nurse and continuity costs are added as they are, patients, nurses and the continuity reference carry their own costs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenaltyConfig {
+    /// Added to the travel time multiplier per broken constraint
+    pub break_factor: f64,
+    /// Per minute care finishes after a hard window's end_time
+    pub lateness: f64,
+    /// Per unit of demand above nurse capacity
+    pub overload: f64,
+    /// Per minute a nurse is back after the depot return time
+    pub depot_return: f64,
+    /// Per minute a synchronized or precedence pair is off
+    pub timing: f64,
+    /// Per patient without a drop cost left in the unassigned pool, which has no travel time to multiply
+    pub missed_visit: f64,
+    /// Per minute a break starts too late or a nurse works longer than allowed without one
+    pub working_time: f64,
+}
+
+impl Default for PenaltyConfig {
+    fn default() -> Self {
+        Self { break_factor: 0.3, lateness: 0.4, overload: 0.4, depot_return: 0.4, timing: 0.4, missed_visit: 1000.0, working_time: 0.4 }
+    }
+}
+
+/// Rounds of re-simulating all routes to let synchronized and precedence-constrained visits wait for each other
+const TIMING_ROUNDS: usize = 16;
+/// Minutes two visits may be apart and still count as synchronized
+const TIMING_TOLERANCE: f64 = 1e-6;
+
+/// Result of simulating one nurse route from the nurse's start location to the end location
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RouteStats {
+    pub travel_time: f64,
+    /// Time spent at patients before their start_time
+    pub waiting_time: f64,
+    /// Sum over patients with hard windows of how long after end_time care finished
+    pub lateness: f64,
+    pub late_visits: u32,
+    /// Visits to patients needing a skill the nurse does not have
+    pub unqualified_visits: u32,
+    /// Minutes care finished after end_time at patients with soft windows
+    pub soft_lateness: f64,
+    /// Minutes care started before start_time at patients with soft-both windows
+    pub earliness: f64,
+    /// Earliness and tardiness cost of the soft windows
+    pub window_cost: f64,
+    pub load: i32,
+    pub overload: i32,
+    pub return_time: f64,
+    /// How long after the end of the shift the nurse is back
+    pub depot_overtime: f64,
+    /// From shift start until back, 0 for an unused nurse
+    pub duration: f64,
+    /// Duration times the nurse's cost per minute
+    pub cost: f64,
+    /// Patients with another nurse than in the continuity reference
+    pub nurse_changes: u32,
+    pub continuity_cost: f64,
+    /// Synchronized and precedence pairs not met whose second visit is on this route
+    pub timing_violations: u32,
+    /// Minutes those pairs are off
+    pub timing_deviation: f64,
+    /// Statistics of the unassigned pool rather than of a nurse route
+    pub pool: bool,
+    /// Patients left in the unassigned pool and their drop cost
+    pub dropped: u32,
+    pub drop_cost: f64,
+    /// Patients without a drop cost left in the pool
+    pub missed_visits: u32,
+    /// Visits before the break and when it starts, None if the nurse takes no break
+    pub break_at: Option<(usize, f64)>,
+    /// Minutes the break starts after the latest start its window allows
+    pub late_break: f64,
+    /// Minutes worked beyond the nurse's maximum working time without a break
+    pub overwork: f64,
+}
+
+impl RouteStats {
+    /// Number of broken constraints: each late, unqualified or missed visit, timing pair not met, overload, late return,
+    /// late break and working too long
+    pub fn violations(&self) -> u32 {
+        self.late_visits + self.unqualified_visits + self.timing_violations + self.missed_visits + (self.overload > 0) as u32 + (self.depot_overtime > 0.0) as u32
+            + (self.late_break > 0.0) as u32 + (self.overwork > 0.0) as u32
+    }
+
+    pub fn is_feasible(&self) -> bool {
+        self.violations() == 0
+    }
+}
+
+/// Arrival, start of care and departure at a single patient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VisitTimes {
+    pub patient: u16,
+    pub arrival: f64,
+    pub start: f64,
+    pub departure: f64,
+}
+
+/// A synchronized or precedence pair that is not met, `minutes` away from what is allowed.
+/// Belongs to the route of the second visit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimingViolation {
+    /// Visits starting apart, or both on one route
+    Synchronized { route: usize, patients: [u16; 2], minutes: f64 },
+    /// `then` starting too soon or too late after `first`
+    Precedence { route: usize, first: u16, then: u16, minutes: f64 },
+}
+
+impl TimingViolation {
+    pub fn route(&self) -> usize {
+        match *self {
+            TimingViolation::Synchronized { route, .. } | TimingViolation::Precedence { route, .. } => route,
+        }
+    }
+
+    pub fn minutes(&self) -> f64 {
+        match *self {
+            TimingViolation::Synchronized { minutes, .. } | TimingViolation::Precedence { minutes, .. } => minutes,
+        }
+    }
+}
+
+/// Single implementation of the route simulation rules, used for fitness, reporting and operators.
+/// Nurses leave their start location at shift start, wait at a patient until its start_time unless the window is soft-both,
+/// and are late when care finishes after end_time, a break for hard windows and a cost for soft ones.
+/// Visiting a patient without having the skills it needs is a break.
+/// Synchronized and precedence-constrained visits wait for the visits they depend on, across routes.
+/// The route after the nurse routes is the pool of unassigned patients, it costs their drop costs
+/// and breaks a constraint for every patient that has to be visited.
+pub struct RouteEvaluator<'a> {
+    t_data: &'a TrainData,
+}
+
+impl<'a> RouteEvaluator<'a> {
+    pub fn new(t_data: &'a TrainData) -> Self {
+        Self { t_data }
+    }
+
+    /// Evaluates `route` driven by the nurse at index `nurse` on its own, without waiting for visits on other routes
+    pub fn evaluate_route(&self, nurse: usize, route: &[u16]) -> RouteStats {
+        self.simulate(nurse, route, None, |_| {})
+    }
+
+    /// Route statistics together with the times of every visit, on its own like `evaluate_route`
+    pub fn schedule_route(&self, nurse: usize, route: &[u16]) -> (RouteStats, Vec<VisitTimes>) {
+        let mut visits: Vec<VisitTimes> = Vec::with_capacity(route.len());
+        let stats = self.simulate(nurse, route, None, |visit| visits.push(visit));
+        (stats, visits)
+    }
+
+    /// Route i is driven by nurse i
+    pub fn evaluate_routes<R: AsRef<[u16]>>(&self, routes: &[R]) -> Vec<RouteStats> {
+        if self.t_data.has_dependencies() {
+            return self.schedule_routes(routes).into_iter().map(|(stats, _)| stats).collect();
+        }
+        routes.iter().enumerate().map(|(nurse, route)| self.evaluate_route(nurse, route.as_ref())).collect()
+    }
+
+    /// Statistics and visit times of every route, route i driven by nurse i.
+    /// Synchronized visits wait for each other and the second visit of a precedence pair waits for the minimum gap,
+    /// for a few rounds of simulating all routes again. Pairs still not met are counted on the route of the second visit
+    pub fn schedule_routes<R: AsRef<[u16]>>(&self, routes: &[R]) -> Vec<(RouteStats, Vec<VisitTimes>)> {
+        let schedule = |release: Option<&[f64]>| -> Vec<(RouteStats, Vec<VisitTimes>)> {
+            routes.iter().enumerate().map(|(nurse, route)| {
+                let mut visits: Vec<VisitTimes> = Vec::with_capacity(route.as_ref().len());
+                let stats = self.simulate(nurse, route.as_ref(), release, |visit| visits.push(visit));
+                (stats, visits)
+            }).collect()
+        };
+        if !self.t_data.has_dependencies() {
+            return schedule(None);
+        }
+
+        let mut release = vec![f64::NEG_INFINITY; self.t_data.nbr_patients() + 1];
+        let mut schedules = schedule(Some(&release));
+        for _ in 0..TIMING_ROUNDS {
+            let starts = visit_starts(&schedules, release.len());
+            let mut changed = false;
+            let mut wait_until = |gene: u16, start: f64, time: f64| if start < time - TIMING_TOLERANCE {
+                release[gene as usize] = time;
+                changed = true;
+            };
+            for &[a, b] in self.t_data.synchronized.iter() {
+                if let (Some((start_a, _)), Some((start_b, _))) = (starts[a as usize], starts[b as usize]) {
+                    wait_until(a, start_a, start_b);
+                    wait_until(b, start_b, start_a);
+                }
+            }
+            for p in self.t_data.precedences.iter() {
+                if let (Some((first, _)), Some((then, _))) = (starts[p.first as usize], starts[p.then as usize]) {
+                    wait_until(p.then, then, first + p.min_gap);
+                    // Too long a gap is closed by starting the first visit later
+                    if let Some(max_gap) = p.max_gap {
+                        wait_until(p.first, first, then - max_gap);
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+            schedules = schedule(Some(&release));
+        }
+
+        for violation in self.timing_violations(&schedules) {
+            let stats = &mut schedules[violation.route()].0;
+            stats.timing_violations += 1;
+            stats.timing_deviation += violation.minutes();
+        }
+        schedules
+    }
+
+    /// Synchronized and precedence pairs the schedules of `schedule_routes` do not meet.
+    /// Pairs with a visit missing from the routes are skipped
+    pub fn timing_violations(&self, schedules: &[(RouteStats, Vec<VisitTimes>)]) -> Vec<TimingViolation> {
+        let starts = visit_starts(schedules, self.t_data.nbr_patients() + 1);
+        let mut violations: Vec<TimingViolation> = Vec::new();
+        for &[a, b] in self.t_data.synchronized.iter() {
+            if let (Some((start_a, route_a)), Some((start_b, route))) = (starts[a as usize], starts[b as usize]) {
+                let minutes = (start_a - start_b).abs();
+                if minutes > TIMING_TOLERANCE || route_a == route {
+                    violations.push(TimingViolation::Synchronized { route, patients: [a, b], minutes });
+                }
+            }
+        }
+        for p in self.t_data.precedences.iter() {
+            if let (Some((first, _)), Some((then, route))) = (starts[p.first as usize], starts[p.then as usize]) {
+                let gap = then - first;
+                let minutes = (p.min_gap - gap).max(0.0) + p.max_gap.map_or(0.0, |max_gap| (gap - max_gap).max(0.0));
+                if minutes > TIMING_TOLERANCE {
+                    violations.push(TimingViolation::Precedence { route, first: p.first, then: p.then, minutes });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Evaluates every nurse route of a 0-delimited chromosome without splitting it into vectors.
+    /// Chromosomes without delimiters are split into routes with push forward insertion.
+    pub fn evaluate_chromosome(&self, chromosome: &Chromosome) -> Vec<RouteStats> {
+        if !chromosome.genes.contains(&0u16) {
+            self.evaluate_routes(&helper::push_forward_insertion(chromosome, self.t_data))
+        } else if self.t_data.has_dependencies() {
+            self.evaluate_routes(&chromosome.genes.split(|&gene| gene == 0).collect::<Vec<&[u16]>>())
+        } else {
+            chromosome.genes.split(|&gene| gene == 0).enumerate().map(|(nurse, route)| self.evaluate_route(nurse, route)).collect()
+        }
+    }
+
+    /// Like `evaluate_chromosome`, but only re-simulates the `touched` routes and copies the rest from `cached`.
+    /// Falls back to a full evaluation if the number of routes differs from the cache,
+    /// or if routes depend on each other through synchronized or precedence pairs.
+    pub fn reevaluate_chromosome(&self, chromosome: &Chromosome, cached: &[RouteStats], touched: &Touched) -> Vec<RouteStats> {
+        if *touched == Touched::All || !chromosome.genes.contains(&0u16) || self.t_data.has_dependencies() {
+            return self.evaluate_chromosome(chromosome);
+        }
+        let routes: Vec<&[u16]> = chromosome.genes.split(|&gene| gene == 0).collect();
+        if routes.len() != cached.len() {
+            return self.evaluate_chromosome(chromosome);
+        }
+        routes.iter()
+            .enumerate()
+            .map(|(index, route)| if touched.contains(index) { self.evaluate_route(index, route) } else { cached[index] })
+            .collect()
+    }
+
+    /// Departure and forward time slack along a route on its own, see [`RouteSlack`]
+    pub fn route_slack(&self, nurse: usize, route: &[u16]) -> RouteSlack {
+        let (stats, visits) = self.schedule_route(nurse, route);
+        let nurse_index = nurse;
+        let nurse = self.t_data.nurse(nurse);
+        let mut departure: Vec<f64> = Vec::with_capacity(route.len() + 1);
+        departure.push(nurse.shift_start);
+        departure.extend(visits.iter().map(|v| v.departure));
+        let mut start: Vec<f64> = visits.iter().map(|v| v.start).collect();
+        start.push(stats.return_time);
+
+        // Backwards from the end location: a delay is absorbed by waiting at the next visit, or has to fit its slack
+        let mut forward_slack = vec![0.0f64; route.len() + 1];
+        forward_slack[route.len()] = nurse.shift_end - stats.return_time;
+        for (i, visit) in visits.iter().enumerate().rev() {
+            let waiting_next = visits.get(i + 1).map_or(0.0, |next| next.start - next.arrival);
+            let patient = self.t_data.patient(visit.patient);
+            let own = if patient.has_hard_window() { patient.end_time - visit.departure } else { f64::INFINITY };
+            forward_slack[i] = own.min(waiting_next + forward_slack[i + 1]);
+        }
+
+        RouteSlack {
+            nurse: nurse_index,
+            route: route.to_vec(),
+            start_location: nurse.start_location,
+            end_location: nurse.end_location,
+            capacity: nurse.capacity,
+            shift_end: nurse.shift_end,
+            departure,
+            start,
+            forward_slack,
+            load: stats.load,
+        }
+    }
+
+    /// Simulates one route. A visit does not start before `release` of its gene, if given.
+    /// A nurse still working when its break window opens takes the break after the visit where it costs least
+    fn simulate<F>(&self, nurse: usize, route: &[u16], release: Option<&[f64]>, on_visit: F) -> RouteStats
+    where F: FnMut(VisitTimes)
+    {
+        if self.t_data.pool_route() == Some(nurse) {
+            return self.unassigned(route);
+        }
+        let shift = self.t_data.nurse(nurse);
+        let rule = match shift.break_rule {
+            Some(rule) if !route.is_empty() && shift.shift_start < rule.end => rule,
+            _ => return self.simulate_break(nurse, route, release, None, on_visit),
+        };
+        let without = self.simulate_break(nurse, route, release, None, |_| {});
+        if without.return_time <= rule.start {
+            return self.simulate_break(nurse, route, release, None, on_visit);
+        }
+        let best = (0..=route.len())
+            .map(|after| (after, self.simulate_break(nurse, route, release, Some(after), |_| {})))
+            .min_by(|(_, a), (_, b)| break_order(a).partial_cmp(&break_order(b)).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(after, _)| after);
+        self.simulate_break(nurse, route, release, best, on_visit)
+    }
+
+    /// Simulates one route with the break after `break_after` visits, at the patient's location or before leaving
+    fn simulate_break<F>(&self, nurse: usize, route: &[u16], release: Option<&[f64]>, break_after: Option<usize>, mut on_visit: F) -> RouteStats
+    where F: FnMut(VisitTimes)
+    {
+        let nurse_index = nurse;
+        let nurse = self.t_data.nurse(nurse);
+        let mut stats = RouteStats::default();
+        let mut time: f64 = nurse.shift_start;
+        let mut location: usize = nurse.start_location;
+
+        for (index, &gene) in route.iter().enumerate() {
+            if break_after == Some(index) {
+                take_break(nurse, index, &mut time, &mut stats);
+            }
+            let patient = self.t_data.patient(gene);
+            let travel_time = self.t_data.travel_time(location, gene as usize, time);
+            stats.travel_time += travel_time;
+
+            let arrival = time + travel_time;
+            let start = release.map_or(patient.care_start(arrival), |release| patient.care_start(arrival).max(release[gene as usize]));
+            let departure = start + patient.care_time;
+            stats.waiting_time += start - arrival;
+            if !patient.has_hard_window() {
+                stats.soft_lateness += (departure - patient.end_time).max(0.0);
+                stats.earliness += (patient.start_time - start).max(0.0);
+                stats.window_cost += patient.window_cost(start, departure);
+            } else if departure > patient.end_time {
+                stats.lateness += departure - patient.end_time;
+                stats.late_visits += 1;
+            }
+            if !self.t_data.qualified(nurse_index, gene) {
+                stats.unqualified_visits += 1;
+            }
+            if let Some(continuity) = self.t_data.continuity() {
+                stats.nurse_changes += continuity.previous_nurse(gene).is_some_and(|previous| previous != nurse_index) as u32;
+                stats.continuity_cost += continuity.cost(nurse_index, gene);
+            }
+            stats.load += patient.demand;
+            on_visit(VisitTimes { patient: gene, arrival, start, departure });
+
+            time = departure;
+            location = gene as usize;
+        }
+
+        if break_after == Some(route.len()) {
+            take_break(nurse, route.len(), &mut time, &mut stats);
+        }
+        if !route.is_empty() {
+            let travel_time = self.t_data.travel_time(location, nurse.end_location, time);
+            stats.travel_time += travel_time;
+            time += travel_time;
+        }
+        stats.return_time = time;
+        if let Some(limit) = nurse.max_working_time {
+            stats.overwork = match (stats.break_at, nurse.break_rule) {
+                (Some((_, start)), Some(rule)) => (start - nurse.shift_start - limit).max(0.0) + (time - start - rule.duration - limit).max(0.0),
+                _ => (time - nurse.shift_start - limit).max(0.0),
+            };
+        }
+        stats.depot_overtime = (time - nurse.shift_end).max(0.0);
+        stats.overload = (stats.load - nurse.capacity).max(0);
+        stats.duration = time - nurse.shift_start;
+        stats.cost = stats.duration * nurse.cost_per_minute;
+        stats
+    }
+}
+
+/// Starts the nurse's break at `time`, or when its window opens
+fn take_break(nurse: &Nurse, after: usize, time: &mut f64, stats: &mut RouteStats) {
+    if let Some(rule) = nurse.break_rule {
+        let start = time.max(rule.start);
+        stats.break_at = Some((after, start));
+        stats.late_break = (start - rule.latest_start()).max(0.0);
+        *time = start + rule.duration;
+    }
+}
+
+/// Break positions are compared by broken constraints, then by how far they are broken, then by return time
+fn break_order(stats: &RouteStats) -> (u32, f64, f64) {
+    (stats.violations(), stats.lateness + stats.depot_overtime + stats.late_break + stats.overwork + stats.window_cost, stats.return_time)
+}
+
+impl RouteEvaluator<'_> {
+    fn unassigned(&self, pool: &[u16]) -> RouteStats {
+        let mut stats = RouteStats { pool: true, dropped: pool.len() as u32, ..RouteStats::default() };
+        for &gene in pool.iter() {
+            match self.t_data.patient(gene).drop_cost {
+                Some(cost) => stats.drop_cost += cost,
+                None => stats.missed_visits += 1,
+            }
+        }
+        stats
+    }
+}
+
+/// Start and route of every visited gene in `schedules`
+fn visit_starts(schedules: &[(RouteStats, Vec<VisitTimes>)], num_genes: usize) -> Vec<Option<(f64, usize)>> {
+    let mut starts: Vec<Option<(f64, usize)>> = vec![None; num_genes];
+    for (route, (_, visits)) in schedules.iter().enumerate() {
+        for visit in visits.iter() {
+            starts[visit.patient as usize] = Some((visit.start, route));
+        }
+    }
+    starts
+}
+
+/// Timing data of a route for constant time insertion checks (Savelsbergh's forward time slack).
+/// Positions are in the route, with the return to the end location as position `route.len()`.
+/// With time-dependent travel a delay can grow on the way, so insertions push it through the rest of the route instead
+
+#[derive(Debug, Clone)]
+pub struct RouteSlack {
+    nurse: usize,
+    route: Vec<u16>,
+    start_location: usize,
+    end_location: usize,
+    capacity: i32,
+    shift_end: f64,
+    /// Departure from the node before each position, the start location at shift start for position 0
+    departure: Vec<f64>,
+    /// Start of care at each position, the return time last
+    start: Vec<f64>,
+    /// How long the start at each position can be delayed without any later visit becoming late
+    /// or the nurse coming back after the shift. Negative if that has already happened
+    forward_slack: Vec<f64>,
+    load: i32,
+}
+
+impl RouteSlack {
+    /// Added travel time of visiting `gene` before `position`, None if that makes a hard window patient late,
+    /// delays a later visit beyond its slack, overloads the nurse or the nurse lacks a skill the patient needs.
+    /// The break stays where the schedule before the insertion has it, and waits for synchronized or
+    /// precedence-constrained visits on other routes are not considered, so a full evaluation may still penalize the result
+    pub fn insertion(&self, gene: u16, position: usize, t_data: &TrainData) -> Option<f64> {
+        let patient = t_data.patient(gene);
+        if self.load + patient.demand > self.capacity || !t_data.qualified(self.nurse, gene) {
+            return None;
+        }
+        let prev = if position == 0 { self.start_location } else { self.route[position - 1] as usize };
+        let next = self.route.get(position).map_or(self.end_location, |&g| g as usize);
+
+        let to_gene = t_data.travel_time(prev, gene as usize, self.departure[position]);
+        let departure = patient.care_start(self.departure[position] + to_gene) + patient.care_time;
+        if patient.has_hard_window() && departure > patient.end_time {
+            return None;
+        }
+        let to_next = t_data.travel_time(gene as usize, next, departure);
+        let arrival_next = departure + to_next;
+        let start_next = if position == self.route.len() { arrival_next } else { t_data.patient(next as u16).care_start(arrival_next) };
+        let fits = if t_data.is_time_dependent() {
+            self.delay_fits(position, start_next, t_data)
+        } else {
+            start_next - self.start[position] <= self.forward_slack[position].max(0.0)
+        };
+        if !fits {
+            return None;
+        }
+        Some(to_gene + to_next - t_data.travel_time(prev, next, self.departure[position]))
+    }
+
+    /// Pushes a new start at `position` through the rest of the route. False if a delayed hard window visit finishes late,
+    /// or the nurse gets back later than both the shift end and the current return
+    fn delay_fits(&self, position: usize, start: f64, t_data: &TrainData) -> bool {
+        let mut start = start;
+        for (index, &gene) in self.route.iter().enumerate().skip(position) {
+            if start <= self.start[index] {
+                return true;
+            }
+            let patient = t_data.patient(gene);
+            let departure = start + patient.care_time;
+            if patient.has_hard_window() && departure > patient.end_time {
+                return false;
+            }
+            let next = self.route.get(index + 1).map_or(self.end_location, |&g| g as usize);
+            let arrival = departure + t_data.travel_time(gene as usize, next, departure);
+            start = if index + 1 == self.route.len() { arrival } else { t_data.patient(next as u16).care_start(arrival) };
+        }
+        start <= self.start[self.route.len()].max(self.shift_end)
+    }
+}
+
+/// Objectives of a solution in multi-objective mode, all minimized
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Objectives {
+    /// Travel time of the nurses only, dropped patients are in `drop_cost`
+    pub travel_time: f64,
+    /// Minutes late at hard and soft windows
+    pub lateness: f64,
+    /// Longest minus shortest nurse working time, from shift start until back, unused nurses work 0
+    pub workload_spread: f64,
+    pub nurses_used: u32,
+    /// Drop cost of the optional patients left unassigned
+    #[serde(default)]
+    pub drop_cost: f64,
+    /// Overload plus depot overtime plus unqualified and missed visits, timing pairs not met and break and working time minutes.
+    /// A constraint rather than an objective, less violation always dominates
+    pub violation: f64,
+}
+
+impl Objectives {
+    pub fn from_routes(stats: &[RouteStats]) -> Self {
+        let workloads = stats.iter().filter(|s| !s.pool).map(|s| s.duration);
+        let longest = workloads.clone().fold(0.0, f64::max);
+        let shortest = workloads.fold(f64::MAX, f64::min).min(longest);
+        Self {
+            travel_time: stats.iter().map(|s| s.travel_time).sum(),
+            lateness: stats.iter().map(|s| s.lateness + s.soft_lateness).sum(),
+            workload_spread: longest - shortest,
+            nurses_used: stats.iter().filter(|s| s.duration > 0.0).count() as u32,
+            drop_cost: stats.iter().map(|s| s.drop_cost).sum(),
+            violation: stats.iter().map(|s| s.overload as f64 + s.depot_overtime + (s.unqualified_visits + s.timing_violations + s.missed_visits) as f64 + s.late_break + s.overwork).sum(),
+        }
+    }
+
+    pub fn values(&self) -> [f64; 5] {
+        [self.travel_time, self.lateness, self.workload_spread, self.nurses_used as f64, self.drop_cost]
+    }
+
+    /// Constrained Pareto dominance: less violation wins, otherwise no worse in every objective and better in one
+    pub fn dominates(&self, other: &Objectives) -> bool {
+        if self.violation != other.violation {
+            return self.violation < other.violation;
+        }
+        let (a, b) = (self.values(), other.values());
+        a.iter().zip(b.iter()).all(|(x, y)| x <= y) && a.iter().zip(b.iter()).any(|(x, y)| x < y)
+    }
+}
+
+/// Returns (fitness, number of broken constraints) for the routes of one solution,
+/// fitness is total travel time with the penalties of `penalty`
+pub fn fitness(stats: &[RouteStats], penalty: &PenaltyConfig) -> (f64, f64) {
+    let mut travel_time = 0.0f64;
+    let mut violations: u32 = 0;
+    let mut weighted = 0.0f64;
+    for route in stats.iter() {
+        travel_time += route.travel_time;
+        violations += route.violations();
+        weighted += penalty.lateness * route.lateness
+            + penalty.overload * route.overload as f64
+            + penalty.depot_return * route.depot_overtime
+            + penalty.timing * route.timing_deviation
+            + penalty.missed_visit * route.missed_visits as f64
+            + penalty.working_time * (route.late_break + route.overwork)
+            + route.window_cost
+            + route.cost
+            + route.continuity_cost
+            + route.drop_cost;
+    }
+    (travel_time * (1.0 + penalty.break_factor * violations as f64) + weighted, violations as f64)
+}
@@ -1,86 +1,387 @@
+use std::cell::RefCell;
+use crate::{evaluator, helper, robustness};
+use crate::config::Objective;
+use crate::evaluator::{PenaltyConfig, RouteEvaluator, RouteStats};
 use crate::individual::chromosome::Chromosome;
 use crate::parsing::TrainData;
 
+/// Objective minimized by [`GenAlg`](crate::gen_alg::GenAlg).
+/// Returns (fitness, number of constraint breaks), a chromosome is feasible when it has no breaks.
 pub trait FitnessFunction {
-    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f32, f32);
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64);
+
+    /// Same as `calculate_fitness`, from already evaluated nurse routes.
+    /// None if the objective needs more than route statistics, otherwise GenAlg evaluates incrementally
+    fn fitness_from_routes(&self, _route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// `calculate_fitness` of a chromosome scored before, e.g. again after the objective changed.
+    /// Objectives that learn from what they evaluate do not count it a second time
+    fn rescore(&self, chromosome: &Chromosome) -> (f64, f64) {
+        self.calculate_fitness(chromosome)
+    }
+
+    /// `fitness_from_routes` of a chromosome scored before, see [`FitnessFunction::rescore`]
+    fn rescore_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        self.fitness_from_routes(route_stats)
+    }
+
+    /// Called by GenAlg after every generation. Returns true when the objective changed,
+    /// so fitness values calculated before are stale
+    fn end_generation(&mut self) -> bool {
+        false
+    }
 }
 
-pub struct TrainDataFitness {
-    t_data: TrainData,
+/// Any closure from chromosome to (fitness, breaks) is a custom objective
+impl<F> FitnessFunction for F
+where F: Fn(&Chromosome) -> (f64, f64)
+{
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64) {
+        self(chromosome)
+    }
+}
+
+/// Penalty-weighted travel time, the default objective
+pub struct TrainDataFitness<'a> {
+    t_data: &'a TrainData,
+    penalty: PenaltyConfig,
+    adaptive: Option<RefCell<AdaptivePenalty>>,
 }
 
-impl TrainDataFitness {
-    pub fn new(t_data: TrainData) -> Self
+impl<'a> TrainDataFitness<'a> {
+    pub fn new(t_data: &'a TrainData) -> Self
     {
-        Self { t_data }
+        Self::with_penalty(t_data, PenaltyConfig::default())
+    }
+
+    pub fn with_penalty(t_data: &'a TrainData, penalty: PenaltyConfig) -> Self
+    {
+        Self { t_data, penalty, adaptive: None }
+    }
+
+    /// Starts from `penalty` and lets `adaptive` adjust the weights between generations
+    pub fn adaptive(t_data: &'a TrainData, penalty: PenaltyConfig, adaptive: AdaptivePenalty) -> Self
+    {
+        Self { t_data, penalty, adaptive: Some(RefCell::new(adaptive)) }
+    }
+
+    #[cfg(test)]
+    pub fn penalty(&self) -> &PenaltyConfig
+    {
+        &self.penalty
     }
 }
-/*
-impl FitnessFunction for TrainDataFitness {
-    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f32, f32) {
-        let mut start: usize = 0;
-        let mut cumulative_breaks: i32 = 0;
-        let mut cumulative_travel_time: f32 = 0.0f32;
-        let travel_matrix: &Vec<Vec<f32>> = &t_data.travel_times;
-
-        let nurse_capacity = t_data.capacity_nurse;
-        let depot_return_time = t_data.depot.return_time;
-
-        // Reset for each nurse (every 0 in chromosome)
-        let mut nurse_load: i32 = 0;
-        let mut current_time: f32 = 0.0f32;
-
-        for gene in chromosome.iter()
-        {
-
-            /// Recalculate time based on travel
-            let travel_time = travel_matrix[start][*gene as usize];
-            current_time += travel_time;
-            cumulative_travel_time += travel_time;
-
-            /// if current gene is 0, we start a new nurse route
-            /// reset time and nurse load, and move to first patient
-            if *gene == 0 {
-                ///Move nurse back to depot and check if back on time
-                if current_time >= depot_return_time {
-                    cumulative_breaks += 1;
-                }
-
-                // Reset nurse variables
-                nurse_load = 0;
-                current_time = 0.0;
-                start = 0;
-                continue
-            }
-            // Fitness is sum of travel times
-            let current_patient = &t_data.patients[&gene.to_string()];
-
-            // Checks if nurse arrives before time window
-            // If so, wait until start of time:_window
-            if current_time < current_patient.start_time {
-                cumulative_breaks += 1;
-                current_time = current_patient.start_time
-            }
-            // Care for patient, and add demand to nurse load
-            current_time += current_patient.care_time;
-            nurse_load += current_patient.demand;
 
-            // If nurse is finished after end_time, add a constraint break
-            if current_time > current_patient.end_time {
-                cumulative_breaks += 1
-            }
+impl FitnessFunction for TrainDataFitness<'_> {
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64) {
+        let stats = RouteEvaluator::new(self.t_data).evaluate_chromosome(chromosome);
+        self.fitness_from_routes(&stats).unwrap()
+    }
+
+    fn fitness_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        if let Some(adaptive) = &self.adaptive {
+            adaptive.borrow_mut().record(route_stats);
+        }
+        self.rescore_from_routes(route_stats)
+    }
 
-            // Check if current nurse over capacity
-            // currently adds one break for every patient attended while over capacity. Maybe change?
-            if nurse_load > nurse_capacity {
-                cumulative_breaks += 1;
+    fn rescore(&self, chromosome: &Chromosome) -> (f64, f64) {
+        let stats = RouteEvaluator::new(self.t_data).evaluate_chromosome(chromosome);
+        self.rescore_from_routes(&stats).unwrap()
+    }
+
+    fn rescore_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        Some(evaluator::fitness(route_stats, &self.penalty))
+    }
+
+    fn end_generation(&mut self) -> bool {
+        match &mut self.adaptive {
+            Some(adaptive) => adaptive.get_mut().update(&mut self.penalty),
+            None => false,
+        }
+    }
+}
+
+/// Adds `fitness_weight` times the expected late visits and minutes of overtime under the instance's
+/// [`Uncertainty`](crate::parsing::Uncertainty) to another objective, so schedules with slack are preferred.
+/// Without a weight it is the other objective. With one every evaluation replays the routes `samples` times
+/// and nothing is cached or evaluated incrementally, so a run takes about `samples` times as long
+pub struct RobustFitness<'a, F> {
+    inner: F,
+    t_data: &'a TrainData,
+}
+
+impl<'a, F> RobustFitness<'a, F> {
+    pub fn new(inner: F, t_data: &'a TrainData) -> Self
+    {
+        Self { inner, t_data }
+    }
+}
+
+impl<F> RobustFitness<'_, F> {
+    fn with_robustness(&self, chromosome: &Chromosome, (fitness, breaks): (f64, f64)) -> (f64, f64) {
+        match self.t_data.uncertainty.as_ref().filter(|u| u.fitness_weight > 0.0) {
+            Some(uncertainty) => {
+                let (routes, _) = helper::split_unassigned(chromosome, self.t_data);
+                let report = robustness::evaluate(&routes, self.t_data, uncertainty);
+                (fitness + uncertainty.fitness_weight * (report.expected_late_visits() + report.total_expected_overtime()), breaks)
             }
+            None => (fitness, breaks),
+        }
+    }
+}
+
+impl<F: FitnessFunction> FitnessFunction for RobustFitness<'_, F> {
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64) {
+        self.with_robustness(chromosome, self.inner.calculate_fitness(chromosome))
+    }
+
+    fn fitness_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        match self.t_data.uncertainty {
+            Some(uncertainty) if uncertainty.fitness_weight > 0.0 => None,
+            _ => self.inner.fitness_from_routes(route_stats),
+        }
+    }
 
-            // Move to next patient
-            start = *gene as usize;
+    fn rescore(&self, chromosome: &Chromosome) -> (f64, f64) {
+        self.with_robustness(chromosome, self.inner.rescore(chromosome))
+    }
+
+    fn rescore_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        match self.t_data.uncertainty {
+            Some(uncertainty) if uncertainty.fitness_weight > 0.0 => None,
+            _ => self.inner.rescore_from_routes(route_stats),
         }
-        (cumulative_travel_time, cumulative_breaks as f32)
+    }
+
+    fn end_generation(&mut self) -> bool {
+        self.inner.end_generation()
     }
 }
-*/
 
+/// Weights [`AdaptivePenalty`] adjusts
+const ADAPTED_WEIGHTS: usize = 6;
+
+/// Adapts the weights of a [`PenaltyConfig`], as in hybrid genetic search: a weight is raised while too few
+/// evaluated individuals satisfy its constraint and lowered while too many do.
+/// `break_factor` follows the share without any violation. `missed_visit` stays fixed,
+/// leaving a mandatory patient unvisited is never worth a shorter route
+#[derive(Debug, Clone)]
+pub struct AdaptivePenalty {
+    /// Wanted share of individuals satisfying each constraint
+    target_feasible: f64,
+    tolerance: f64,
+    increase: f64,
+    decrease: f64,
+    /// Generations between weight updates
+    interval: usize,
+    min_weight: f64,
+    max_weight: f64,
+
+    /// Generations left until the next update
+    countdown: usize,
+    evaluated: usize,
+    /// Individuals satisfying each constraint of `feasible_fractions` since the last update
+    satisfied: [usize; ADAPTED_WEIGHTS],
+}
+
+impl AdaptivePenalty {
+    pub fn new(target_feasible: f64, interval: usize) -> Self {
+        assert!((0.0..=1.0).contains(&target_feasible));
+        assert!(interval > 0);
+        Self {
+            target_feasible,
+            tolerance: 0.05,
+            increase: 1.2,
+            decrease: 0.85,
+            interval,
+            min_weight: 0.01,
+            max_weight: 1000.0,
+            countdown: interval,
+            evaluated: 0,
+            satisfied: [0; ADAPTED_WEIGHTS],
+        }
+    }
+
+    pub fn record(&mut self, stats: &[RouteStats]) {
+        self.evaluated += 1;
+        let satisfied = [
+            stats.iter().all(|s| s.late_visits == 0),
+            stats.iter().all(|s| s.overload == 0),
+            stats.iter().all(|s| s.depot_overtime <= 0.0),
+            stats.iter().all(|s| s.timing_violations == 0),
+            stats.iter().all(|s| s.late_break <= 0.0 && s.overwork <= 0.0),
+            stats.iter().all(RouteStats::is_feasible),
+        ];
+        for (count, ok) in self.satisfied.iter_mut().zip(satisfied.iter()) {
+            *count += *ok as usize;
+        }
+    }
+
+    /// Share of individuals recorded since the last update satisfying the (lateness, overload, depot return,
+    /// timing, working time, all) constraints
+    pub fn feasible_fractions(&self) -> [f64; ADAPTED_WEIGHTS] {
+        let evaluated = self.evaluated.max(1) as f64;
+        self.satisfied.map(|count| count as f64 / evaluated)
+    }
+
+    /// Counts a generation, every `interval` generations adjusts the weights and starts a new tally.
+    /// Returns true if any weight changed.
+    pub fn update(&mut self, penalty: &mut PenaltyConfig) -> bool {
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return false;
+        }
+        self.countdown = self.interval;
+        if self.evaluated == 0 {
+            return false;
+        }
+
+        let fractions = self.feasible_fractions();
+        let mut weights = [
+            &mut penalty.lateness,
+            &mut penalty.overload,
+            &mut penalty.depot_return,
+            &mut penalty.timing,
+            &mut penalty.working_time,
+            &mut penalty.break_factor,
+        ];
+        let mut changed = false;
+        for (weight, fraction) in weights.iter_mut().zip(fractions.iter()) {
+            let factor = if *fraction < self.target_feasible - self.tolerance {
+                self.increase
+            } else if *fraction > self.target_feasible + self.tolerance {
+                self.decrease
+            } else {
+                continue;
+            };
+            let adjusted = (**weight * factor).max(self.min_weight).min(self.max_weight);
+            changed |= adjusted != **weight;
+            **weight = adjusted;
+        }
+
+        self.evaluated = 0;
+        self.satisfied = [0; ADAPTED_WEIGHTS];
+        changed
+    }
+}
+
+impl Default for AdaptivePenalty {
+    fn default() -> Self {
+        Self::new(0.2, 10)
+    }
+}
+
+/// Pure total travel time, constraint breaks are counted but not penalized
+pub struct DistanceFitness<'a> {
+    t_data: &'a TrainData,
+}
+
+impl<'a> DistanceFitness<'a> {
+    pub fn new(t_data: &'a TrainData) -> Self
+    {
+        Self { t_data }
+    }
+}
+
+impl FitnessFunction for DistanceFitness<'_> {
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64) {
+        let stats = RouteEvaluator::new(self.t_data).evaluate_chromosome(chromosome);
+        self.fitness_from_routes(&stats).unwrap()
+    }
+
+    fn fitness_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        // Unassigned patients cost their drop cost instead of travel
+        let travel_time: f64 = route_stats.iter().map(|s| s.travel_time + s.drop_cost).sum();
+        let breaks: u32 = route_stats.iter().map(RouteStats::violations).sum();
+        Some((travel_time, breaks as f64))
+    }
+}
+
+/// Fewer constraint breaks always wins, travel time only decides between equally many breaks
+pub struct LexicographicFitness<'a> {
+    t_data: &'a TrainData,
+    /// Larger than the travel time of any solution: the longest edge out of every patient,
+    /// plus the longest edge out of every nurse's start location, driven at the slowest speed, and every drop cost
+    break_weight: f64,
+}
+
+impl<'a> LexicographicFitness<'a> {
+    pub fn new(t_data: &'a TrainData) -> Self
+    {
+        let longest_edge = |row: &Vec<f64>| row.iter().cloned().fold(0.0, f64::max);
+        let longest_trips = t_data.travel_times.iter().skip(1).map(longest_edge).sum::<f64>()
+            + (0..t_data.nbr_nurses as usize).map(|n| longest_edge(&t_data.travel_times[t_data.nurse(n).start_location])).sum::<f64>();
+        let break_weight = longest_trips * t_data.slowest_factor()
+            + t_data.patients.values().filter_map(|p| p.drop_cost).sum::<f64>();
+        Self { t_data, break_weight }
+    }
+}
+
+impl FitnessFunction for LexicographicFitness<'_> {
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64) {
+        let stats = RouteEvaluator::new(self.t_data).evaluate_chromosome(chromosome);
+        self.fitness_from_routes(&stats).unwrap()
+    }
+
+    fn fitness_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        let (travel_time, breaks) = DistanceFitness::new(self.t_data).fitness_from_routes(route_stats)?;
+        Some((breaks * self.break_weight + travel_time, breaks))
+    }
+}
+
+/// Fitness function of the objective chosen in [`GaConfig`](crate::config::GaConfig)
+pub enum ObjectiveFitness<'a> {
+    Penalty(TrainDataFitness<'a>),
+    Distance(DistanceFitness<'a>),
+    Lexicographic(LexicographicFitness<'a>),
+}
+
+impl<'a> ObjectiveFitness<'a> {
+    /// `adaptive` lets the penalty objective adapt its weights, the other objectives have none
+    pub fn new(t_data: &'a TrainData, objective: Objective, adaptive: bool) -> Self
+    {
+        match objective {
+            Objective::Penalty if adaptive => Self::Penalty(TrainDataFitness::adaptive(t_data, PenaltyConfig::default(), AdaptivePenalty::default())),
+            Objective::Penalty => Self::Penalty(TrainDataFitness::new(t_data)),
+            Objective::Distance => Self::Distance(DistanceFitness::new(t_data)),
+            Objective::Lexicographic => Self::Lexicographic(LexicographicFitness::new(t_data)),
+        }
+    }
+
+    fn inner(&self) -> &dyn FitnessFunction {
+        match self {
+            Self::Penalty(f) => f,
+            Self::Distance(f) => f,
+            Self::Lexicographic(f) => f,
+        }
+    }
+}
+
+impl FitnessFunction for ObjectiveFitness<'_> {
+    fn calculate_fitness(&self, chromosome: &Chromosome) -> (f64, f64) {
+        self.inner().calculate_fitness(chromosome)
+    }
+
+    fn fitness_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        self.inner().fitness_from_routes(route_stats)
+    }
+
+    fn rescore(&self, chromosome: &Chromosome) -> (f64, f64) {
+        self.inner().rescore(chromosome)
+    }
+
+    fn rescore_from_routes(&self, route_stats: &[RouteStats]) -> Option<(f64, f64)> {
+        self.inner().rescore_from_routes(route_stats)
+    }
+
+    fn end_generation(&mut self) -> bool {
+        match self {
+            Self::Penalty(f) => f.end_generation(),
+            Self::Distance(f) => f.end_generation(),
+            Self::Lexicographic(f) => f.end_generation(),
+        }
+    }
+}
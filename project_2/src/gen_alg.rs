@@ -1,30 +1,39 @@
+use std::cell::Cell;
 use rand::{Rng, RngCore};
 use rand::seq::SliceRandom;
 use crate::parsing::TrainData;
 use crate::crossover::Crossover;
-use crate::{helper, MIN_POP_DEV, P_MUT_MIN, XOVER_PROB};
+use crate::evaluator::{RouteEvaluator, RouteStats};
+use crate::helper;
+use crate::config::GaConfig;
 use crate::helper::hamming_distance;
-use crate::individual::individual::{calculate_fitness, Individual};
-use crate::mutation::{MutationHolder};
+use crate::fitness::FitnessFunction;
+use crate::individual::chromosome::Chromosome;
+use crate::individual::individual::Individual;
+use crate::mutation::{MutationHolder, Touched};
 use crate::selection::{general_crowding, ParentSelection, SurvivorSelection};
 
 
 
-pub struct GenAlg<'a, S, P> {
+pub struct GenAlg<'a, S, P, F> {
     survivor_selection_method: S,
     parent_selection_method: P,
     crossover_method: Box<dyn Crossover>,
+    fitness_function: F,
 
     t_data: &'a TrainData,
-    pop_size_multiplier: usize,
-    m_method_vec: MutationHolder
+    config: GaConfig,
+    m_method_vec: MutationHolder,
+    /// Cleared once the fitness function turns out not to work on route statistics
+    incremental: Cell<bool>,
 }
-impl<'a, S, P> GenAlg<'a, S, P>
-where S: SurvivorSelection, P: ParentSelection
+impl<'a, S, P, F> GenAlg<'a, S, P, F>
+where S: SurvivorSelection, P: ParentSelection, F: FitnessFunction
 {
     pub fn new(survivor_selection_method: S,
                parent_selection_method: P,
                crossover_method: impl Crossover + 'static,
+               fitness_function: F,
                t_data: &'a TrainData,
                m_method_vec: MutationHolder
 
@@ -34,75 +43,180 @@ where S: SurvivorSelection, P: ParentSelection
             survivor_selection_method,
             parent_selection_method,
             crossover_method: Box::new(crossover_method),
+            fitness_function,
             t_data,
-            pop_size_multiplier: 5,
-            m_method_vec
+            config: GaConfig::default(),
+            m_method_vec,
+            incremental: Cell::new(true),
         }
     }
 
+    /// Replaces the default mutation, crossover and offspring parameters
+    pub fn with_config(mut self, config: GaConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     pub fn evolve<I>(&mut self, rng: &mut dyn RngCore, population: &[I]) -> Vec<I>
     where I: Individual,
     {
         assert!(!population.is_empty());
-        let mut std = helper::pop_std_dev(&population);
-        if std >= MIN_POP_DEV
-        {
-            std = MIN_POP_DEV;
-        }
-        let p = P_MUT_MIN + 0.1 * (MIN_POP_DEV  - std);
-        self.m_method_vec.adjust_chances(p);
-        let mut new_pop: Vec<I> = (0..(population.len() * self.pop_size_multiplier))
+        // Parents from outside GenAlg, e.g. the initial population, migrants or restarts, may lack objectives
+        let evaluated: Vec<I>;
+        let population = if !I::MULTI_OBJECTIVE || population.iter().all(|i| i.objectives().is_some()) {
+            population
+        } else {
+            evaluated = population.iter().map(|i| self.copy_individual(i)).collect();
+            &evaluated
+        };
+        let std = helper::pop_std_dev(population);
+        self.m_method_vec.adjust_chances(self.config.mutation_probability(std));
+        self.parent_selection_method.prepare(population);
+        let crowding = self.survivor_selection_method.crowding_replacement();
+        let mut new_pop: Vec<I> = (0..(population.len() * self.config.pop_size_multiplier))
             .map(|_| {  // iterator from function
                 let parents = self.parent_selection_method.select(rng, population);
 
                 // Create offspring proportional with XOVER probabilty
-                if rng.gen_bool(XOVER_PROB)
+                if rng.gen_bool(self.config.xover_prob)
                 {
                     // create child from crossover of parents
                     let mut child = self
                         .crossover_method
-                        .crossover(rng, parents[0].chromosome(), parents[1].chromosome(), &self.t_data);
+                        .crossover(rng, parents[0].chromosome(), parents[1].chromosome(), self.t_data);
+
+                    // Routes the child does not share with the first parent need to be simulated again
+                    let mut touched = match parents[0].route_stats() {
+                        Some(_) => Touched::between(parents[0].chromosome(), &child),
+                        None => Touched::All,
+                    };
 
                     // Run anywhere from 0 to num_mutator mutations
                     for _ in 0..rng.gen_range(0..self.m_method_vec.len())
                     {
-                        self.m_method_vec[rng.gen_range(0..self.m_method_vec.len())].mutate(rng, &mut child);
+                        touched = touched.merge(self.m_method_vec[rng.gen_range(0..self.m_method_vec.len())].mutate(rng, &mut child));
                     }
 
-                    let (fitness, feasible) = calculate_fitness(&child, &self.t_data);
-                    //println!("{:?}", &fitness);
-                    // create child individual from chromosome
-                    // (parents, I::create(child, fitness))
-
                     // Crowding with CF = 1
-                    let child_individual = I::create(child, fitness, !(feasible > 0.0) );
+                    let child_individual: I = self.create_individual(child, parents[0].route_stats(), &touched);
+                    if !crowding
+                    {
+                        return child_individual;
+                    }
                     let competitor = if hamming_distance(parents[0].chromosome(), child_individual.chromosome()) < hamming_distance(parents[1].chromosome(), child_individual.chromosome()) { parents[0] } else { parents[1] };
 
-                    let m = general_crowding::<I>(competitor, &child_individual, 0.0, rng).clone();
-                    if m == 0 { child_individual } else { I::create(competitor.chromosome().clone(), competitor.fitness(), !(feasible > 0.0)) }
+                    let m = general_crowding::<I>(competitor, &child_individual, 0.0, rng);
+                    if m == 0 { child_individual } else { self.copy_individual(competitor) }
                 }
                 // If not crossover, equal chance of choosing either parent
                 else {
-                    if rng.gen_bool(0.5) {I::create(parents[0].chromosome().clone(), parents[0].fitness(), parents[0].feasible())} else {  I::create(parents[1].chromosome().clone(), parents[1].fitness(),parents[1].feasible()) }
+                    if rng.gen_bool(0.5) { self.copy_individual(parents[0]) } else { self.copy_individual(parents[1]) }
                 }
 
 
             })
             .collect(); // Collect into collection of individuals
 
+        if self.survivor_selection_method.keeps_parents()
+        {
+            new_pop.extend(population.iter().map(|i| self.copy_individual(i)));
+        }
+
+        // Penalty weights changed, fitness of the offspring is stale
+        if self.fitness_function.end_generation()
+        {
+            new_pop = new_pop.iter().map(|i| self.rescore_individual(i.chromosome().clone(), i.route_stats())).collect();
+        }
 
-        //new_pop.extend(population.iter().map(|a| I::create(a.chromosome().clone(), a.fitness())));
 
         //Cull population using survivor selection method
         self.survivor_selection_method.select(rng, &mut new_pop, population.len());
         new_pop
     }
 
+    /// Scores new offspring. With a fitness function on route statistics only the touched routes
+    /// are simulated again, the rest are taken from `cached`. Debug builds check the operators kept it a valid chromosome
+    fn create_individual<I>(&self, chromosome: Chromosome, cached: Option<&[RouteStats]>, touched: &Touched) -> I
+    where I: Individual
+    {
+        debug_assert!(helper::valid_chromosome(&chromosome, self.t_data), "crossover or mutation broke {:?}", chromosome.genes);
+        self.score(chromosome, cached, touched, false)
+    }
+
+    /// Scores an individual evaluated before, which the fitness function does not count again
+    fn rescore_individual<I>(&self, chromosome: Chromosome, cached: Option<&[RouteStats]>) -> I
+    where I: Individual
+    {
+        self.score(chromosome, cached, &Touched::Unchanged, true)
+    }
+
+    fn score<I>(&self, chromosome: Chromosome, cached: Option<&[RouteStats]>, touched: &Touched, again: bool) -> I
+    where I: Individual
+    {
+        if self.incremental.get()
+        {
+            let evaluator = RouteEvaluator::new(self.t_data);
+            let route_stats = match cached {
+                Some(cached) => evaluator.reevaluate_chromosome(&chromosome, cached, touched),
+                None => evaluator.evaluate_chromosome(&chromosome),
+            };
+            let scored = if again { self.fitness_function.rescore_from_routes(&route_stats) } else { self.fitness_function.fitness_from_routes(&route_stats) };
+            if let Some((fitness, breaks)) = scored
+            {
+                let mut individual = I::create(chromosome, fitness, breaks <= 0.0);
+                individual.set_route_stats(route_stats);
+                return individual;
+            }
+            self.incremental.set(false);
+        }
+        let (fitness, breaks) = if again { self.fitness_function.rescore(&chromosome) } else { self.fitness_function.calculate_fitness(&chromosome) };
+        self.with_objectives(I::create(chromosome, fitness, breaks <= 0.0))
+    }
+
+    /// Scores an individual from elsewhere, e.g. a migrant scored with the weights of another island
+    pub fn rescore<I>(&self, individual: &I) -> I
+    where I: Individual
+    {
+        self.rescore_individual(individual.chromosome().clone(), individual.route_stats())
+    }
+
+    /// Adds route statistics, and so objectives, to a multi-objective individual scored without them
+    pub fn with_objectives<I>(&self, mut individual: I) -> I
+    where I: Individual
+    {
+        if I::MULTI_OBJECTIVE && individual.objectives().is_none()
+        {
+            individual.set_route_stats(RouteEvaluator::new(self.t_data).evaluate_chromosome(individual.chromosome()));
+        }
+        individual
+    }
+
+    /// Copies an individual with its cached route stats, evaluating them if it has none yet
+    fn copy_individual<I>(&self, individual: &I) -> I
+    where I: Individual
+    {
+        match individual.route_stats()
+        {
+            Some(route_stats) => {
+                let mut copy = I::create(individual.chromosome().clone(), individual.fitness(), individual.feasible());
+                copy.set_route_stats(route_stats.to_vec());
+                copy
+            }
+            None if self.incremental.get() => self.rescore_individual(individual.chromosome().clone(), None),
+            None => self.with_objectives(I::create(individual.chromosome().clone(), individual.fitness(), individual.feasible())),
+        }
+    }
+
     pub fn t_data(&self) -> &TrainData
     {
         self.t_data
     }
 
+    pub fn fitness_function(&self) -> &F
+    {
+        &self.fitness_function
+    }
+
 }
 
 
@@ -1,6 +1,8 @@
 
 use ordered_float::OrderedFloat;
+use std::ops::Range;
 use std::string::String;
+use crate::evaluator::{RouteEvaluator, RouteSlack};
 use crate::individual::chromosome::Chromosome;
 use crate::individual::individual::Individual;
 use crate::parsing::TrainData;
@@ -24,6 +26,8 @@ where I: Individual
     (sum / (population.len() - 1) as f64).sqrt()
 }
 
+/// Nurse routes of a chromosome, one per delimiter plus one. A chromosome without delimiters is a single route,
+/// use [`split_unassigned`] to spread it over the nurses
 pub fn split_into_nurses(chromosome: &Chromosome) -> Vec<Vec<u16>> {
 
     if chromosome.genes.contains(&0u16)
@@ -43,14 +47,41 @@ pub fn split_into_nurses(chromosome: &Chromosome) -> Vec<Vec<u16>> {
         split
     }
     else {
-        //push_forward_insertion()
-        todo!()
+        vec![chromosome.genes.clone()]
     }
 
 }
 
+/// Nurse routes of a delimited chromosome and the patients in the unassigned pool after them.
+/// Chromosomes without delimiters are split with push forward insertion and leave nobody unassigned
+pub fn split_unassigned(chromosome: &Chromosome, t_data: &TrainData) -> (Vec<Vec<u16>>, Vec<u16>) {
+    if !chromosome.genes.contains(&0u16) {
+        return (push_forward_insertion(chromosome, t_data), Vec::new());
+    }
+    let mut routes = split_into_nurses(chromosome);
+    let unassigned = match t_data.pool_route() {
+        Some(pool) if routes.len() > pool => routes.pop().unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    (routes, unassigned)
+}
+
+/// Gene index range of every nurse route in a chromosome, without copying the routes.
+/// A chromosome without delimiters is a single route
+pub fn route_ranges(chromosome: &Chromosome) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut start: usize = 0;
+    for (index, gene) in chromosome.iter().enumerate() {
+        if *gene == 0 {
+            ranges.push(start..index);
+            start = index + 1;
+        }
+    }
+    ranges.push(start..chromosome.len());
+    ranges
+}
 
-pub fn combine_into_chromo(split: &Vec<Vec<u16>>) -> Chromosome {
+pub fn combine_into_chromo(split: &[Vec<u16>]) -> Chromosome {
     let mut t: Vec<u16> = Vec::new();
     for nurse in split.iter()
     {
@@ -71,16 +102,10 @@ where I: Individual
     population.iter().min_by_key(|a| OrderedFloat(a.fitness())).unwrap()
 }
 
-pub fn best_k_individuals<I>(population: &[I]) -> &I
-    where I: Individual
-{
-    todo!()
-}
-
 pub fn keep_best_n<I>(population: &mut Vec<I>, n: usize)
 where I: Individual
 {
-    population.sort_by(|a, b| OrderedFloat(a.fitness()).cmp(&OrderedFloat(b.fitness())));
+    population.sort_by_key(|a| OrderedFloat(a.fitness()));
     population.truncate(n)
 }
 
@@ -104,52 +129,147 @@ pub fn hamming_distance(h1: &Chromosome, h2: &Chromosome) -> u16
     h_dist
 }
 
-pub fn gen_solution_string(solution: &Chromosome, t_data: &TrainData) -> String
+///Checks that a chromosome is a valid genome for the given instance:
+///every patient 1..=n occurs exactly once, and there is exactly one 0-delimiter between each nurse route
+pub fn valid_chromosome(chromosome: &Chromosome, t_data: &TrainData) -> bool
 {
-    let split = split_into_nurses(solution);
+    let num_patients = t_data.nbr_patients();
+    if chromosome.len() != num_patients + t_data.nbr_delimiters()
+    {
+        return false;
+    }
+
+    let mut seen = vec![false; num_patients + 1];
+    let mut delimiters: usize = 0;
+    for gene in chromosome.iter()
+    {
+        let gene = *gene as usize;
+        if gene == 0 {
+            delimiters += 1;
+        } else if gene > num_patients || seen[gene] {
+            return false;
+        } else {
+            seen[gene] = true;
+        }
+    }
+    delimiters == t_data.nbr_delimiters()
+}
+
+/// Readable schedule of a chromosome, reporting `fitness` as the value the run scored it with
+pub fn gen_solution_string(solution: &Chromosome, t_data: &TrainData, fitness: f64) -> String
+{
+    let (split, unassigned) = split_unassigned(solution, t_data);
+    let evaluator = RouteEvaluator::new(t_data);
 
     let mut total_travel_time = 0.0f64;
-    let t_matrix = &t_data.travel_times;
+    let mut total_window_cost = 0.0f64;
+    let mut total_continuity_cost = 0.0f64;
+    let mut nurse_changes: u32 = 0;
+    let mut timing_violations: u32 = 0;
+    let mut late_breaks: u32 = 0;
+    let mut overworked: u32 = 0;
     let mut string_builder: String = String::new();
-    for (nurse, route) in split.iter().enumerate() {
-
-        let mut time:f64 = 0.0;
-        let mut prev_patient: u16 = 0;
-        let mut current_nurse_demand = 0;
-        let mut path_string: String = String::from("D(0)");
-        for gene in route.iter()
+    let schedules = evaluator.schedule_routes(&split);
+    for (nurse, (route, (stats, visits))) in split.iter().zip(schedules.iter()).enumerate() {
+
+        total_travel_time += stats.travel_time;
+        total_window_cost += stats.window_cost;
+        total_continuity_cost += stats.continuity_cost;
+        nurse_changes += stats.nurse_changes;
+        timing_violations += stats.timing_violations;
+        late_breaks += (stats.late_break > 0.0) as u32;
+        overworked += (stats.overwork > 0.0) as u32;
+
+        let start = t_data.nurse(nurse);
+        let mut path_string: String = format!("{}({})", location_name(start.start_location), start.shift_start);
+        // Breaks are shown between the visits, with a ! if they start too late
+        let break_string = |(_, break_start): (usize, f64)| {
+            let duration = start.break_rule.map_or(0.0, |rule| rule.duration);
+            format!("->Break({:.2}-{:.2}){}", break_start, break_start + duration, if stats.late_break > 0.0 { "!" } else { "" })
+        };
+        for (index, visit) in visits.iter().enumerate()
         {
-            let current_patient = &t_data.patients[&gene.to_string()];
-            let arrival_time = time + t_matrix[prev_patient as usize][*gene as usize];
-            let departure_time = arrival_time + current_patient.care_time;
-
-            time = departure_time;
-            total_travel_time += t_matrix[prev_patient as usize][*gene as usize];
-            current_nurse_demand +=  current_patient.demand;
-
-            path_string.push_str(&String::from(format!("->{}({:.2}-{:.2})[{}-{}]", &gene, arrival_time, departure_time, &current_patient.start_time, current_patient.end_time)));
-            prev_patient = *gene;
+            if let Some(rest) = stats.break_at.filter(|&(after, _)| after == index)
+            {
+                path_string.push_str(&break_string(rest));
+            }
+            let current_patient = t_data.patient(visit.patient);
+            path_string.push_str(&format!("->{}({:.2}-{:.2})[{}-{}]", visit.patient, visit.start, visit.departure, current_patient.start_time, current_patient.end_time));
+            // Soft windows are marked with the cost of the visit
+            if !current_patient.has_hard_window()
+            {
+                path_string.push_str(&format!("~{:.2}", current_patient.window_cost(visit.start, visit.departure)));
+            }
+            if !t_data.qualified(nurse, visit.patient)
+            {
+                path_string.push('!');
+            }
+            // Patients with a new nurse are marked with the nurse they had before
+            if let Some(previous) = t_data.continuity().and_then(|c| c.previous_nurse(visit.patient)).filter(|&p| p != nurse)
+            {
+                path_string.push_str(&format!("@{}", previous));
+            }
+        }
+        let route_time = stats.duration;
+        if let Some(rest) = stats.break_at.filter(|&(after, _)| after == visits.len())
+        {
+            path_string.push_str(&break_string(rest));
         }
-            let route_time =  if !route.is_empty() {time + t_matrix[route[route.len() - 1] as usize][0]} else {0.0};
         if !route.is_empty()
         {
-            total_travel_time += t_matrix[*route.last().unwrap() as usize][0];
-            path_string.push_str(&String::from(format!("->D({})", route_time)));
+            path_string.push_str(&format!("->{}({:.2})", location_name(start.end_location), stats.return_time));
         }
-        path_string.push_str("\n");
+        path_string.push('\n');
 
-        path_string.insert_str(0, &String::from(format!("{}\t", current_nurse_demand)));
-        path_string.insert_str(0, &String::from(format!("{:.2}\t", route_time)));
-        path_string.insert_str(0, &String::from(format!("Nurse {:0>2}\t", nurse)));
+        // A heterogeneous fleet shows every nurse's capacity and shift instead of the header
+        if t_data.nurses.is_some()
+        {
+            path_string.insert_str(0, &format!("{}/{}\tShift {}-{}\t", stats.load, start.capacity, start.shift_start, start.shift_end));
+        }
+        else
+        {
+            path_string.insert_str(0, &format!("{}\t", stats.load));
+        }
+        path_string.insert_str(0, &format!("{:.2}\t", route_time));
+        path_string.insert_str(0, &format!("Nurse {:0>2}\t", nurse));
 
         string_builder.push_str(&path_string);
 
     }
-    string_builder.push_str(&String::from(format!("\n------------------------------------------------------------------\n")));
-    string_builder.push_str(&String::from(format!("Objective value (total travel time): {}", total_travel_time)));
-    string_builder.insert_str(0, &String::from(format!("\n-------------------------------------------\n")));
-    string_builder.insert_str(0, &String::from(format!("\nDepot return time: {}", t_data.depot.return_time)));
-    string_builder.insert_str(0, &String::from(format!("Nurse capacity: {}",  t_data.capacity_nurse)));
+    string_builder.push_str("\n------------------------------------------------------------------\n");
+    string_builder.push_str(&format!("Objective value (total travel time): {}", total_travel_time));
+    string_builder.push_str(&format!("\nFitness (travel time with penalties and costs): {:.2}", fitness));
+    if total_window_cost > 0.0
+    {
+        string_builder.push_str(&format!("\nSoft time window cost: {:.2}", total_window_cost));
+    }
+    if timing_violations > 0
+    {
+        string_builder.push_str(&format!("\nSynchronized or precedence pairs not met: {}", timing_violations));
+    }
+    if late_breaks + overworked > 0
+    {
+        string_builder.push_str(&format!("\nNurses with a late break: {}, working too long without a break: {}", late_breaks, overworked));
+    }
+    if t_data.continuity().is_some()
+    {
+        string_builder.push_str(&format!("\nContinuity cost: {:.2} ({} patients with another nurse)", total_continuity_cost, nurse_changes));
+    }
+    if let Some(pool) = t_data.pool_route()
+    {
+        let stats = evaluator.evaluate_route(pool, &unassigned);
+        string_builder.push_str(&format!("\nUnassigned patients: {:?} (drop cost {:.2})", unassigned, stats.drop_cost));
+        if stats.missed_visits > 0
+        {
+            string_builder.push_str(&format!("\nMandatory patients unassigned: {}", stats.missed_visits));
+        }
+    }
+    if t_data.nurses.is_none()
+    {
+        string_builder.insert_str(0, "\n-------------------------------------------\n");
+        string_builder.insert_str(0, &format!("\nDepot return time: {}", t_data.depot.return_time));
+        string_builder.insert_str(0, &format!("Nurse capacity: {}",  t_data.capacity_nurse));
+    }
 
 
 
@@ -157,103 +277,85 @@ pub fn gen_solution_string(solution: &Chromosome, t_data: &TrainData) -> String
 
 }
 
+/// D for the depot, L and the node index for other nurse start and end locations
+fn location_name(location: usize) -> String
+{
+    if location == 0 { String::from("D") } else { format!("L{}", location) }
+}
+
 pub fn push_forward_insertion(chromo: &Chromosome, t_data: &TrainData) -> Vec<Vec<u16>>
 {
-    let mut routes: Vec<Vec<u16>> = vec![vec![0u16; 2]; t_data.nbr_nurses as usize];
+    let mut routes: Vec<Vec<u16>> = vec![Vec::new(); t_data.nbr_nurses as usize];
+    let evaluator = RouteEvaluator::new(t_data);
 
-    let mut route_counter:usize = 0;
+    // Slack of every route, rebuilt only after an insertion into it
+    let mut slacks: Vec<Option<RouteSlack>> = vec![None; routes.len()];
 
-    let travel_matrix = &t_data.travel_times;
-    //TODO: fix demand
-    let mut demand = 0;
-    let mut chromo_iter = chromo.iter();
-    let mut gene = chromo_iter.next().unwrap();
-    loop
+    let mut route_counter:usize = 0;
+    let last_route = routes.len() - 1;
+    // Patients the current nurse lacks the skills for, placed once the routes are built
+    let mut deferred: Vec<u16> = Vec::new();
+    let mut chromo_iter = chromo.iter().peekable();
+    while let Some(&&gene) = chromo_iter.peek()
     {
-        let mut lowest_cost: f64 = f64::MAX;
-        let mut best_index: usize = usize::MAX;
-        let mut route_demand: i32 = 0;
-
-        let current_patient = &t_data.patients[&gene.to_string()];
-        route_demand += current_patient.demand;
-
-        let t = routes.len();
-        if route_counter >= t - 1
-        {
-            routes[t - 1].push(*gene);
-            gene = chromo_iter.next().unwrap_or(&u16::MAX);
-            if *gene == u16::MAX
-            {
-                break
-            }
-            continue;
-        }
-        if routes[route_counter].len() == 2
+        if !t_data.qualified(route_counter.min(last_route), gene) && t_data.qualifications().qualified_nurses(gene).next().is_some()
         {
-            // Add edge from depot to first patient
-            routes[route_counter].insert(1, *gene);
-            gene = chromo_iter.next().unwrap_or(&u16::MAX);
-            if *gene == u16::MAX
-            {
-                break
-            }
+            deferred.push(gene);
+            chromo_iter.next();
             continue;
         }
 
-        let mut route_iter = routes[route_counter].iter();
-        let mut origin = route_iter.next().unwrap();
-
-        for (i, destination) in route_iter.enumerate()
+        // Last nurse takes every patient left
+        if route_counter >= last_route || routes[route_counter].is_empty()
         {
-            let origin_end_time = if *origin == 0 {0.0f64} else {t_data.patients[&origin.to_string()].end_time};
-            let destination_start_time = if *destination == 0 {t_data.depot.return_time} else {t_data.patients[&destination.to_string()].start_time};
-            let patient_end = t_data.patients[&gene.to_string()].end_time;
-            let patient_start =t_data.patients[&gene.to_string()].start_time;
-            let travel_origin_patient = travel_matrix[*origin as usize][*gene as usize];
-            let travel_patient_destination = travel_matrix[*gene as usize][*destination as usize];
-
-            if !(origin_end_time + travel_origin_patient < patient_start && patient_end + travel_patient_destination < destination_start_time)
-            {
-                origin = destination;
-                continue;
-            }
-
-            let t_time: f64 = travel_matrix[*gene as usize][*destination as usize];
-
-            if OrderedFloat(t_time) <= OrderedFloat(lowest_cost)
-            {
-                lowest_cost = t_time;
-                best_index = i;
-            }
-
-            origin = destination;
+            routes[route_counter.min(last_route)].push(gene);
+            chromo_iter.next();
+            continue;
         }
 
-        // If gene fits into route, insert it,
+        // Cheapest position the patient fits without breaking the route's time windows or capacity,
         // else current route full, go to next
-        if best_index < usize::MAX
-        {
-            routes[route_counter].insert(best_index + 1, *gene);
-            gene = chromo_iter.next().unwrap_or(&u16::MAX);
-            if *gene == u16::MAX
-            {
-                break
+        let slack = slacks[route_counter].get_or_insert_with(|| evaluator.route_slack(route_counter, &routes[route_counter]));
+        let best = (0..=routes[route_counter].len())
+            .filter_map(|position| slack.insertion(gene, position, t_data).map(|cost| (position, cost)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((position, _)) => {
+                routes[route_counter].insert(position, gene);
+                slacks[route_counter] = None;
+                chromo_iter.next();
             }
-        } else {
-            route_counter += 1;
+            None => route_counter += 1,
         }
     }
 
-    let t = routes.len();
-    for (i, route) in routes.iter_mut().enumerate()
+    // Cheapest feasible position with a qualified nurse, else the end of the first qualified nurse's route
+    for gene in deferred
     {
-        if !(i == t - 1)
+        let mut best: Option<(usize, usize, f64)> = None;
+        for nurse in t_data.qualifications().qualified_nurses(gene)
         {
-            route.remove(0);
-            route.pop();
-        } else {
-            route.retain(|&x| x != 0);
+            let slack = slacks[nurse].get_or_insert_with(|| evaluator.route_slack(nurse, &routes[nurse]));
+            for position in 0..=routes[nurse].len()
+            {
+                if let Some(cost) = slack.insertion(gene, position, t_data)
+                {
+                    if best.is_none_or(|b| cost < b.2)
+                    {
+                        best = Some((nurse, position, cost));
+                    }
+                }
+            }
         }
+        let (nurse, position) = match best {
+            Some((nurse, position, _)) => (nurse, position),
+            None => {
+                let nurse = t_data.qualifications().qualified_nurses(gene).next().unwrap_or(last_route);
+                (nurse, routes[nurse].len())
+            }
+        };
+        routes[nurse].insert(position, gene);
+        slacks[nurse] = None;
     }
     routes
 }
\ No newline at end of file
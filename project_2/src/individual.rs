@@ -1,28 +1,44 @@
 pub(crate) mod individual {
-    use crate::helper;
+    use crate::evaluator;
+    use crate::evaluator::{Objectives, PenaltyConfig, RouteEvaluator, RouteStats};
     use crate::individual::chromosome::Chromosome;
     use crate::parsing::TrainData;
 
 
     pub trait Individual{
+        /// Selected by objectives, which come from route statistics, rather than by fitness alone
+        const MULTI_OBJECTIVE: bool = false;
+
         fn create(chromosome: Chromosome, fitness: f64, feasible: bool) -> Self;
         fn chromosome(&self) -> &Chromosome;
         fn fitness(&self) -> f64;
         fn feasible(&self) -> bool;
+
+        /// Evaluation of every nurse route, cached so GenAlg only re-simulates the routes a mutation touched
+        fn route_stats(&self) -> Option<&[RouteStats]> {
+            None
+        }
+        fn set_route_stats(&mut self, _route_stats: Vec<RouteStats>) {}
+
+        /// Objective vector for multi-objective selection, None for single objective individuals
+        fn objectives(&self) -> Option<&Objectives> {
+            None
+        }
     }
 
     #[derive(Clone, Debug)]
     pub struct Route {
         fitness: f64,
         chromosome: Chromosome,
-        feasible: bool
+        feasible: bool,
+        route_stats: Option<Vec<RouteStats>>,
         //parents: Option<Chromosome>
     }
     impl Route {
         pub fn new(chromosome: Chromosome, t_data: &TrainData) -> Self
         {
             let (fitness, feasible) = calculate_fitness(&chromosome, t_data);
-            Self { fitness, chromosome, feasible: !(feasible > 0.0) }
+            Self { fitness, chromosome, feasible: feasible <= 0.0, route_stats: None }
         }
     }
 
@@ -32,6 +48,7 @@ pub(crate) mod individual {
                 fitness,
                 chromosome,
                 feasible,
+                route_stats: None,
             }
         }
 
@@ -46,180 +63,72 @@ pub(crate) mod individual {
         fn feasible(&self) -> bool {
             self.feasible
         }
-    }
-
-
 
-
-    ///Returns a tuple containing (total travel time, adjusted_fitness)
-    ///total travel time only calculates time traveled
-    ///adjusted fitness includes penalty for constraint breaks
-    pub(crate) fn calculate_fitness(chromosome: &Chromosome, t_data: &TrainData) -> (f64, f64) {
-
-        if chromosome.genes.contains(&0u16)
-        {
-            calculate_fitness_delims(chromosome, t_data)
-        }
-        else {
-            calculate_fitness_no_delims(chromosome, t_data)
+        fn route_stats(&self) -> Option<&[RouteStats]> {
+            self.route_stats.as_deref()
         }
 
+        fn set_route_stats(&mut self, route_stats: Vec<RouteStats>) {
+            self.route_stats = Some(route_stats);
+        }
     }
 
-    fn calculate_fitness_delims(chromosome: &Chromosome, t_data: &TrainData) -> (f64, f64) {
-        let mut start: usize = 0;
-        let mut cumulative_breaks: i32 = 0;
-        let mut cumulative_travel_time: f64 = 0.0;
-        let travel_matrix: &Vec<Vec<f64>> = &t_data.travel_times;
-
-        let mut penalty: f64 = 0.0;
 
-        let nurse_capacity = t_data.capacity_nurse;
-        let depot_return_time = t_data.depot.return_time;
 
-        // Reset for each nurse (every 0 in chromosome)
-        let mut nurse_load: i32 = 0;
-        let mut current_time: f64 = 0.0;
 
-        for gene in chromosome.iter()
-        {
-
-            // Recalculate time based on travel
-            let travel_time = travel_matrix[start][*gene as usize];
+    /// Route with an objective vector, derived from its route stats, for NSGA-II
+    #[derive(Clone, Debug)]
+    pub struct MultiObjectiveRoute {
+        fitness: f64,
+        chromosome: Chromosome,
+        feasible: bool,
+        route_stats: Option<Vec<RouteStats>>,
+        objectives: Option<Objectives>,
+    }
 
-            current_time += travel_time;
-            cumulative_travel_time += travel_time;
+    impl Individual for MultiObjectiveRoute {
+        const MULTI_OBJECTIVE: bool = true;
 
-            // if current gene is 0, we start a new nurse route
-            // reset time and nurse load, and move to first patient
-            if *gene == 0 {
-                if nurse_load > nurse_capacity  {
-                    penalty += (nurse_load - nurse_capacity) as f64 * 0.4; //Multiplier?
-                    cumulative_breaks += 1;
-                }
-                //Move nurse back to depot and check if back on time
-                if current_time > depot_return_time {
-                    penalty += (current_time - depot_return_time) * 0.4; //Multiplier?
-                    cumulative_breaks += 1;
-                }
-                // Reset nurse variables
-                nurse_load = 0;
-                current_time = 0.0;
-                start = 0;
-                continue
-            }
-            // Fitness is sum of travel times
-            let current_patient = &t_data.patients[&gene.to_string()];
-
-            // Checks if nurse arrives before time window
-            // If so, wait until start of time:_window
-            if current_time < current_patient.start_time {
-                //penalty += (current_patient.start_time - current_time) * 0.005;// * 15.0; //Multiplier?
-                current_time = current_patient.start_time
-            }
-            // Care for patient, and add demand to nurse load
-            current_time += current_patient.care_time;
-            nurse_load += current_patient.demand;
-
-            // If nurse is finished after end_time, add a constraint break
-            if current_time > current_patient.end_time {
-                penalty += (current_time - current_patient.end_time) * 0.4 ;//  * 15.0; //Multiplier?
-                cumulative_breaks += 1
+        fn create(chromosome: Chromosome, fitness: f64, feasible: bool) -> Self {
+            Self {
+                fitness,
+                chromosome,
+                feasible,
+                route_stats: None,
+                objectives: None,
             }
-
-            // Move to next patient
-            start = *gene as usize;
         }
 
-
-        //Add return to depot for last nurse
-        cumulative_travel_time += travel_matrix[start][0];
-
-
-        if nurse_load > nurse_capacity  {
-            penalty += (nurse_load - nurse_capacity) as f64 * 0.15; //Multiplier?
-            cumulative_breaks += 1;
-        }
-        //Move nurse back to depot and check if back on time
-        if current_time >= depot_return_time {
-            penalty += current_time - depot_return_time * 0.4; //Multiplier?
-            cumulative_breaks += 1;
+        fn chromosome(&self) -> &Chromosome {
+            &self.chromosome
         }
-        //(cumulative_travel_time + 13.0f32 * cumulative_breaks as f32, cumulative_breaks as f32)
-        //(cumulative_breaks as f32, cumulative_breaks as f32)
-        //(cumulative_travel_time + penalty  as f32, cumulative_breaks as f32)
-        (cumulative_travel_time * ( 1.0 + 0.3*cumulative_breaks as f64)  as f64, cumulative_breaks as f64)
-        //(cumulative_travel_time + penalty * 20.0 as f64, cumulative_breaks as f64)
-    }
-    fn calculate_fitness_no_delims(chromosome: &Chromosome, t_data: &TrainData) -> (f64, f64) {
-
-        let nurses = helper::push_forward_insertion(chromosome, t_data);
-
-        let mut start: usize = 0;
-        let mut cumulative_breaks: i32 = 0;
-        let mut cumulative_travel_time: f64 = 0.0;
-        let travel_matrix: &Vec<Vec<f64>> = &t_data.travel_times;
 
-        let mut penalty: f64 = 0.0;
-
-        let nurse_capacity = t_data.capacity_nurse;
-        let depot_return_time = t_data.depot.return_time;
+        fn fitness(&self) -> f64 {
+            self.fitness
+        }
 
-        // Reset for each nurse (every 0 in chromosome)
-        let mut nurse_load: i32 = 0;
-        let mut current_time: f64 = 0.0;
+        fn feasible(&self) -> bool {
+            self.feasible
+        }
 
-        for route in nurses.iter()
-        {
-            for gene in route.iter()
-            {
-                // Recalculate time based on travel
-                let travel_time = travel_matrix[start][*gene as usize];
-
-                current_time += travel_time;
-                cumulative_travel_time += travel_time;
-
-                // Fitness is sum of travel times
-                let current_patient = &t_data.patients[&gene.to_string()];
-
-                // Checks if nurse arrives before time window
-                // If so, wait until start of time:_window
-                if current_time < current_patient.start_time {
-                    //penalty += (current_patient.start_time - current_time) * 0.005;// * 15.0; //Multiplier?
-                    current_time = current_patient.start_time
-                }
-                // Care for patient, and add demand to nurse load
-                current_time += current_patient.care_time;
-                nurse_load += current_patient.demand;
-
-                // If nurse is finished after end_time, add a constraint break
-                if current_time > current_patient.end_time {
-                    penalty += (current_time - current_patient.end_time) * 0.3 ;//  * 15.0; //Multiplier?
-                    cumulative_breaks += 1
-                }
+        fn route_stats(&self) -> Option<&[RouteStats]> {
+            self.route_stats.as_deref()
+        }
 
-                // Move to next patient
-                start = *gene as usize;
-            }
-            if nurse_load > nurse_capacity  {
-                penalty += (nurse_load - nurse_capacity) as f64 * 0.3; //Multiplier?
-                cumulative_breaks += 1;
-            }
-            //Move nurse back to depot and check if back on time
-            if current_time > depot_return_time {
-                penalty += (current_time - depot_return_time) * 0.3; //Multiplier?
-                cumulative_breaks += 1;
-            }
-            // Reset nurse variables
-            nurse_load = 0;
-            current_time = 0.0;
-            start = 0;
-            //Add return to depot for last nurse
-            cumulative_travel_time += travel_matrix[start][0];
+        fn set_route_stats(&mut self, route_stats: Vec<RouteStats>) {
+            self.objectives = Some(Objectives::from_routes(&route_stats));
+            self.route_stats = Some(route_stats);
+        }
 
+        fn objectives(&self) -> Option<&Objectives> {
+            self.objectives.as_ref()
         }
+    }
 
-        (cumulative_travel_time + penalty as f64, cumulative_breaks as f64)
+    ///Returns a tuple containing (fitness, number of constraint breaks)
+    ///fitness is total travel time, with the default penalties for constraint breaks
+    pub(crate) fn calculate_fitness(chromosome: &Chromosome, t_data: &TrainData) -> (f64, f64) {
+        evaluator::fitness(&RouteEvaluator::new(t_data).evaluate_chromosome(chromosome), &PenaltyConfig::default())
     }
 }
 
@@ -1,13 +1,13 @@
 use rand::{RngCore};
 use rand::prelude::IteratorRandom;
 //TODO: gjør ferdig dette axel
-///Unfinished kMeans implementation for clustering
+//Unfinished kMeans implementation for clustering
 
 fn fast_euclid(a_0: &u16, b_0: & u16, a_1: &u16, b_1: &u16) -> f32 {
     ((a_0-b_0).pow(2) as f32 + (a_1-b_1).pow(2) as f32).sqrt()
 }
 
-pub fn point_mean(points: &Vec<(u16, u16)>) -> (f32, f32) {
+pub fn point_mean(points: &[(u16, u16)]) -> (f32, f32) {
     let x_sum: f32 = points.iter().map(|(a, b)| a).sum::<u16>() as f32 / points.len() as f32;
     let y_sum: f32 = points.iter().map(|(a, b)| b).sum::<u16>() as f32 / points.len() as f32;
 
@@ -4,7 +4,8 @@ use std::{env, thread};
 use std::process::Command;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
-use rand::{Rng, thread_rng};
+use rand::{Rng, SeedableRng, thread_rng};
+use rand_chacha::ChaCha8Rng;
 
 mod mutation;
 mod gen_alg;
@@ -19,47 +20,52 @@ mod fitness;
 mod population_init;
 mod helper;
 mod kmeans;
+mod error;
+mod validation;
+mod solution;
+mod checker;
+mod evaluator;
+mod periodic;
+mod robustness;
+mod reoptimize;
+mod config;
 
 use std::time::{Instant};
 
 use rand::seq::IteratorRandom;
 use rand_chacha::rand_core::RngCore;
+use crate::config::{GaConfig, GaConfigBuilder};
+use crate::error::IoError;
+use crate::evaluator::PenaltyConfig;
+use crate::fitness::FitnessFunction;
 use crate::gen_alg::GenAlg;
-use crate::individual::individual::{Individual, Route};
+use crate::individual::individual::{Individual, MultiObjectiveRoute, Route};
 use crate::mutation::{MutationHolder};
 use crate::population_init::pop_init::PopulationGenerator;
 use crate::selection::{ParentSelection, SurvivorSelection};
 
 //const PYTHON_SCRIPT_PATH: &str = r"C:\Users\Axel\PycharmProjects\axel_tools\bio_ai\bio_ai_plotter.py";
 const PYTHON_SCRIPT_PATH: &str = r"plotting\bio_ai_plotter.py";
-const MIN_POP_DEV:f64 = 4.0;
-const P_MUT_MIN: f64 = 0.07;
-const XOVER_PROB: f64 = 0.97;
 
-pub fn island<I, S, P, G>(mut population: Vec<I>, mut gen_alg: GenAlg<S, P>, pop_gen: G, pop_size: usize, rng: &mut dyn RngCore, sender: Sender<Vec<I>>, receiver: Receiver<Vec<I>>) -> I
-    where P: ParentSelection, S: SurvivorSelection, I: Individual, G: PopulationGenerator,
+/// Evolves one island for `config.generations` generations and returns its final population
+pub fn island<I, S, P, F, G>(mut population: Vec<I>, mut gen_alg: GenAlg<S, P, F>, pop_gen: G, config: &GaConfig, rng: &mut dyn RngCore, sender: Sender<Vec<I>>, receiver: Receiver<Vec<I>>) -> Vec<I>
+    where P: ParentSelection, S: SurvivorSelection, F: FitnessFunction, I: Individual, G: PopulationGenerator,
 {
-
-    const SEND_INTERVAL:usize = 150;
-    const NUM_SEND_I:usize = 5;
-
     let mut prev: f64 = 0.0;
-    let mut stagnation_counter: u8 = 0;
-    for i in 0..1 00 {
+    for i in 0..config.generations {
         //println!("{:?}", helper::avg_fitness(&population));
         population = gen_alg.evolve(rng, &population);
         //let best_solution= helper::best_fitness(&population);
         //println!("{:?}", calculate_fitness(&best_solution.chromosome(), &data));
 
 
-        if i % 10 == 0
+        if i % config.stagnation_interval == 0
         {
             let current_avg = helper::avg_fitness(&population);
             if prev == current_avg
             {
                 helper::keep_best_n::<I>(&mut population, 2);
-                population.extend(pop_gen.generate_population::<I>(gen_alg.t_data(), pop_size - 2, rng));
-                stagnation_counter = 0;
+                population.extend(pop_gen.generate_population::<I, F>(gen_alg.t_data(), config.pop_size - 2, gen_alg.fitness_function(), rng));
             }
             prev = current_avg;
         }
@@ -69,9 +75,9 @@ pub fn island<I, S, P, G>(mut population: Vec<I>, mut gen_alg: GenAlg<S, P>, pop
             println!("Gen {}", i);
         }
 
-        if i % SEND_INTERVAL == 0
+        if i % config.send_interval == 0
         {
-            let sample: Vec<&I> = population.iter().choose_multiple(rng, NUM_SEND_I);
+            let sample: Vec<&I> = population.iter().choose_multiple(rng, config.migrants);
             let mut vec: Vec<I> = Vec::new();
             for i in sample.iter()
             {
@@ -86,107 +92,473 @@ pub fn island<I, S, P, G>(mut population: Vec<I>, mut gen_alg: GenAlg<S, P>, pop
         }
 
         //population.extend(msg);
+        // Migrants arrive with the fitness of the island they left
         for x in receiver.try_iter()
         {
-            population.extend(x);
+            population.extend(x.iter().map(|migrant| gen_alg.rescore(migrant)));
         }
     }
 
     println!("Goodbye.");
-    let best_solution= helper::best_fitness(&population);
-    I::create(best_solution.chromosome().clone(), best_solution.fitness(), best_solution.feasible())
-
+    // Migrants and restarts arrive without objectives
+    population.into_iter().map(|individual| gen_alg.with_objectives(individual)).collect()
 }
 
-pub fn setup_tx_rx<I>(n_threads: usize) -> (Vec<Sender<Vec<I>>>, Vec<Receiver<Vec<I>>>)
+type Channels<I> = (Vec<Sender<Vec<I>>>, Vec<Receiver<Vec<I>>>);
+
+pub fn setup_tx_rx<I>(n_threads: usize) -> Channels<I>
 where I: Individual {
-    let (senders, receivers): (Vec<Sender<Vec<I>>>, Vec<Receiver<Vec<I>>>) = (0..n_threads).into_iter().map(|_| mpsc::channel()).unzip();
-    (senders, receivers)
+    (0..n_threads).map(|_| mpsc::channel()).unzip()
 }
 
-pub fn mutation_vec(rng: &mut dyn RngCore) -> MutationHolder
+/// Cross-route operators respect nurse skills if any patient needs one, and leave the unassigned pool to the pool operators
+pub fn mutation_vec(rng: &mut dyn RngCore, data: &parsing::TrainData) -> MutationHolder
 {
     let mut holder = MutationHolder::new();
     //holder.register(mutation::InversionMutation::new(0.5));
     //holder.register(mutation::ScrambleMutation::new(0.5));
-    holder.register(mutation::cross_route::CrossRouteInsertMutation::new(rng.gen_range(0.06..0.15)));
-    holder.register(mutation::cross_route::CrossRouteSwapMutation::new(rng.gen_range(0.06..0.15)));
+    let (mut insert, mut swap) = if data.qualifications().is_restricted() {
+        let qualifications = data.qualifications();
+        (mutation::cross_route::CrossRouteInsertMutation::with_qualifications(rng.gen_range(0.06..0.15), qualifications.clone()),
+         mutation::cross_route::CrossRouteSwapMutation::with_qualifications(rng.gen_range(0.06..0.15), qualifications.clone()))
+    } else {
+        (mutation::cross_route::CrossRouteInsertMutation::new(rng.gen_range(0.06..0.15)),
+         mutation::cross_route::CrossRouteSwapMutation::new(rng.gen_range(0.06..0.15)))
+    };
+    if data.has_optional_patients() {
+        insert = insert.excluding_pool();
+        swap = swap.excluding_pool();
+    }
+    holder.register(insert);
+    holder.register(swap);
     holder.register(mutation::in_route::InRouteSwapMutation::new(rng.gen_range(0.06..0.15)));
     holder.register(mutation::in_route::InRouteInsertMutation::new(rng.gen_range(0.06..0.15)));
     holder.register(mutation::in_route::InRouteInversionMutation::new(rng.gen_range(0.06..0.15)));
     holder.register(mutation::in_route::InRouteScrambleMutation::new(rng.gen_range(0.06..0.15)));
+    if data.has_optional_patients() {
+        let optional: Vec<bool> = (0..=data.nbr_patients() as u16).map(|gene| gene != 0 && data.patient(gene).drop_cost.is_some()).collect();
+        holder.register(mutation::pool::PoolRemoveMutation::new(rng.gen_range(0.06..0.15), optional));
+        holder.register(mutation::pool::PoolInsertMutation::new(rng.gen_range(0.06..0.15)));
+    }
     holder
 }
 
-fn main() {
-    const NTHREADS: usize = 8;
+/// Audits every solution in a solution file against the instance and prints a report per solution
+fn check_solution_file(solution_path: &str, data: &parsing::TrainData)
+{
+    let plans = match parsing::parse_solution_file(solution_path) {
+        Ok(plans) => plans,
+        Err(e) => {
+            eprintln!("Unable to load solution {}: {}", solution_path, e);
+            std::process::exit(1);
+        }
+    };
+    for (i, plan) in plans.iter().enumerate()
+    {
+        if let Some(name) = plan.instance_name.as_ref().filter(|n| **n != data.instance_name)
+        {
+            eprintln!("Solution {} is for instance {}, checking against {}", i, name, data.instance_name);
+        }
+        let report = checker::check_routes(&plan.routes, data);
+        println!("Solution {}", i);
+        if let Some(fitness) = plan.reported_fitness
+        {
+            println!("Reported fitness: {}", fitness);
+        }
+        println!("{}", report);
+    }
+}
 
-    let (mut senders, receivers) = setup_tx_rx(NTHREADS);
-    senders.rotate_left(1);
-    //let island_configs = setup_island_configs();
+/// Value following `flag` on the command line
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String>
+{
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1))
+}
 
-    let args: Vec<String> = env::args().collect();
-    assert!(args.len() > 1);
-    let filepath = &args[1];
-    let data = parsing::parse_json(&filepath.clone());
+/// Loads the single solution in a solution file as the continuity of care reference
+fn set_previous_solution(data: &mut parsing::TrainData, solution_path: &str, change_cost: f64)
+{
+    let plans = match parsing::parse_solution_file(solution_path) {
+        Ok(plans) => plans,
+        Err(e) => {
+            eprintln!("Unable to load previous solution {}: {}", solution_path, e);
+            std::process::exit(1);
+        }
+    };
+    if plans.len() != 1
+    {
+        eprintln!("Previous solution {} holds {} solutions, expected one", solution_path, plans.len());
+        std::process::exit(1);
+    }
+    data.set_previous_routes(&plans[0].routes, change_cost);
+}
 
+/// Plans every day of a multi-day instance with [`periodic::improve`] and writes the plan to week_solution.json
+fn run_week(data: &parsing::TrainData, seed: u64, config: &GaConfig, start: Instant)
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mutations = mutation_vec(&mut rng, data);
+    let plan = periodic::WeekPlan::random(&mut rng, data);
+    let plan = periodic::improve(&mut rng, plan, data, &mutations, &PenaltyConfig::default(), config.week_iterations);
+
+    let mut week = plan.week_solution(data);
+    week.seed = Some(seed);
+    week.run_time_secs = start.elapsed().as_secs_f64();
+    println!("Elapsed: {:.2?}", start.elapsed());
+    println!("Travel time over {} days: {:.2}, feasible: {}", week.days.len(), week.objective, week.feasible);
+    if let Err(e) = parsing::write_week_json(&week)
+    {
+        eprintln!("{}", e);
+    }
+}
 
-    let now = Instant::now();
+/// Patient ids in a comma separated list following `flag`, empty without the flag
+fn patient_list(args: &[String], flag: &str) -> Vec<u16>
+{
+    flag_value(args, flag).map_or(Vec::new(), |list| list.split(',').map(|p| p.trim().parse().unwrap_or_else(|_| {
+        eprintln!("{} takes comma separated patient ids, got {}", flag, list);
+        std::process::exit(1);
+    })).collect())
+}
+
+/// Plans again what is left at `--clock` of the solution in a solution.json, with the `--add` patients
+/// and without the `--cancel` ones, and writes the new plan to solution.json
+fn run_reoptimize(data: &parsing::TrainData, solution_path: &str, args: &[String], seed: u64, config: &GaConfig, start: Instant)
+{
+    let solution = parsing::parse_solution_json(solution_path).unwrap_or_else(|e| {
+        eprintln!("Unable to load solution {}: {}", solution_path, e);
+        std::process::exit(1);
+    });
+    let clock: f64 = match flag_value(args, "--clock") {
+        Some(clock) => clock.parse().unwrap_or_else(|_| {
+            eprintln!("Clock must be a number, got {}", clock);
+            std::process::exit(1);
+        }),
+        None => {
+            eprintln!("--reoptimize needs the current time as --clock <time>");
+            std::process::exit(1);
+        }
+    };
+    let mut disruption = reoptimize::Disruption::at(clock, &solution);
+    disruption.added = patient_list(args, "--add");
+    disruption.cancelled = patient_list(args, "--cancel");
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut plan = match reoptimize::reoptimize(&mut rng, data, &solution, &disruption, config) {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("Unable to re-plan {}: {}", solution_path, e);
+            std::process::exit(1);
+        }
+    };
+    plan.seed = Some(seed);
+    plan.run_time_secs = start.elapsed().as_secs_f64();
+    println!("Elapsed: {:.2?}", start.elapsed());
+    println!("Re-planned {} fixed visits at {}: travel time {:.2}, feasible: {}", disruption.fixed.iter().sum::<usize>(), clock, plan.objective, plan.feasible);
+    if let Err(e) = parsing::write_solution_json(&plan)
+    {
+        eprintln!("{}", e);
+    }
+}
+
+/// GA parameters from the `--config` file or the defaults, with `--<field> <value>` overrides such as `--pop-size 100`
+fn ga_config(args: &[String]) -> GaConfig
+{
+    fn parsed<T: std::str::FromStr>(flag: &str, value: &str) -> T
+    {
+        value.parse().unwrap_or_else(|_| {
+            eprintln!("{} must be a number, got {}", flag, value);
+            std::process::exit(1);
+        })
+    }
+
+    let mut builder = match flag_value(args, "--config") {
+        Some(path) => GaConfig::load(path).unwrap_or_else(|e| {
+            eprintln!("Unable to load config {}: {}", path, e);
+            std::process::exit(1);
+        }).into_builder(),
+        None => GaConfig::builder(),
+    };
+    type Setter<T> = fn(GaConfigBuilder, T) -> GaConfigBuilder;
+    let rates: [(&str, Setter<f64>); 4] = [
+        ("--min-pop-dev", GaConfigBuilder::min_pop_dev),
+        ("--p-mut-min", GaConfigBuilder::p_mut_min),
+        ("--xover-prob", GaConfigBuilder::xover_prob),
+        ("--continuity-cost", GaConfigBuilder::continuity_cost),
+    ];
+    let counts: [(&str, Setter<usize>); 11] = [
+        ("--pop-size-multiplier", GaConfigBuilder::pop_size_multiplier),
+        ("--pop-size", GaConfigBuilder::pop_size),
+        ("--generations", GaConfigBuilder::generations),
+        ("--islands", GaConfigBuilder::islands),
+        ("--send-interval", GaConfigBuilder::send_interval),
+        ("--migrants", GaConfigBuilder::migrants),
+        ("--stagnation-interval", GaConfigBuilder::stagnation_interval),
+        ("--tournament-size", GaConfigBuilder::tournament_size),
+        ("--reoptimize-pop-size", GaConfigBuilder::reoptimize_pop_size),
+        ("--reoptimize-generations", GaConfigBuilder::reoptimize_generations),
+        ("--week-iterations", GaConfigBuilder::week_iterations),
+    ];
+    for (flag, set) in rates.iter()
+    {
+        if let Some(value) = flag_value(args, flag)
+        {
+            builder = set(builder, parsed(flag, value));
+        }
+    }
+    for (flag, set) in counts.iter()
+    {
+        if let Some(value) = flag_value(args, flag)
+        {
+            builder = set(builder, parsed(flag, value));
+        }
+    }
+    if let Some(value) = flag_value(args, "--objective")
+    {
+        builder = builder.objective(value.parse().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }));
+    }
+    if args.iter().any(|a| a == "--adaptive-penalty")
+    {
+        builder = builder.adaptive_penalty(true);
+    }
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("Invalid GA config: {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Runs the islands in parallel threads and returns their final populations
+fn run_islands<I, S, P>(data: &parsing::TrainData, seed: u64, config: &GaConfig, survivor_selection: fn() -> S, parent_selection: fn(usize) -> P) -> Vec<Vec<I>>
+where I: Individual + Send + 'static, S: SurvivorSelection + 'static, P: ParentSelection + 'static
+{
+    let (mut senders, receivers) = setup_tx_rx(config.islands);
+    senders.rotate_left(1);
+    //let island_configs = setup_island_configs();
 
     let children : Vec<_> = senders
         .into_iter()
-        .zip(receivers.into_iter())
+        .zip(receivers)
         .enumerate()
-        .map(|(_, (tx, rx))| {
+        .map(|(i, (tx, rx))| {
             let d = data.clone();
+            let config = config.clone();
             thread::spawn(move || {
-                let mut rng = thread_rng();
+                let mut rng = ChaCha8Rng::seed_from_u64(seed.wrapping_add(i as u64));
 
-                let population = population_init::pop_init::init_pop_random::<Route>(&d, 300, &mut rng);
+                let fitness_function = fitness::RobustFitness::new(fitness::ObjectiveFitness::new(&d, config.objective, config.adaptive_penalty), &d);
+                let population = population_init::pop_init::init_pop_random::<I, _>(&d, config.pop_size, &fitness_function, &mut rng);
                 let algo = gen_alg::GenAlg::new(
-                    selection::ElitismSurvivorSelection::new(),
-                    selection::TournamentParentSelection::new(2),
+                    survivor_selection(),
+                    parent_selection(config.tournament_size),
                     crossover::OrderOneCrossover::new(),
-                    &d,     mutation_vec(&mut rng),
-                );
+                    fitness_function,
+                    &d,     mutation_vec(&mut rng, &d),
+                ).with_config(config.clone());
 
-                let t = island(population, algo, population_init::pop_init::RandomPopulation::new(), 300, &mut rng, tx, rx);
-                t
+                island(population, algo, population_init::pop_init::RandomPopulation::new(), &config, &mut rng, tx, rx)
                 //println!("thread {} sent: {}", i, i);
                 //println!("thread {} recv: {:?}", i, rx.recv().unwrap());
             })
         }).collect();
 
-    let mut best_solutions: Vec<Route> = Vec::new();
-    for child in children {
-        best_solutions.push(child.join().unwrap());
-    }
+    children.into_iter().map(|child| child.join().unwrap()).collect()
+}
+
+/// The islands' objective at the default weights. Islands adapting their weights score on different scales,
+/// their final solutions are compared and reported with this one
+fn reference_fitness<'a>(data: &'a parsing::TrainData, config: &GaConfig) -> fitness::RobustFitness<'a, fitness::ObjectiveFitness<'a>>
+{
+    fitness::RobustFitness::new(fitness::ObjectiveFitness::new(data, config.objective, false), data)
+}
+
+/// Writes the best solution of every island to solution.txt, and the overall best feasible one
+/// to solution_detail.txt and solution.json
+fn write_solutions<I>(populations: &[Vec<I>], data: &parsing::TrainData, config: &GaConfig, seed: u64, start: Instant)
+where I: Individual
+{
+    let reference = reference_fitness(data, config);
+    let best_solutions: Vec<(&I, f64, bool)> = populations.iter()
+        .map(|p| p.iter()
+            .map(|i| {
+                let (fitness, breaks) = reference.calculate_fitness(i.chromosome());
+                (i, fitness, breaks <= 0.0)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap())
+        .collect();
 
-    let mut b: &Route = &best_solutions[0];
+    let (mut b, mut b_fitness, _) = best_solutions[0];
     let mut sol_string = String::from(&data.instance_name) + "\n";
-    for solution in best_solutions.iter()
+    for &(solution, fitness, feasible) in best_solutions.iter()
     {
-        if solution.feasible()
+        if feasible && fitness < b_fitness
         {
-            if solution.fitness() < b.fitness()
-            {
-                b = solution;
-            }
-
+            b = solution;
+            b_fitness = fitness;
+        }
+        // The unassigned pool is written on its own line instead of as the last route
+        let (routes, unassigned) = helper::split_unassigned(solution.chromosome(), data);
+        let routes_string = match data.pool_route() {
+            Some(_) => serde_json::to_string(&routes).unwrap_or_default(),
+            None => solution.chromosome().format_chromosome(),
+        };
+        sol_string.push_str(&(routes_string + "\n" + &fitness.to_string() + " " +  &feasible.to_string() + "\n"));
+        if data.pool_route().is_some()
+        {
+            sol_string.push_str(&format!("Unassigned: {:?}\n", unassigned));
         }
-        sol_string.push_str( &String::from(solution.chromosome().format_chromosome() + "\n" + &solution.fitness().to_string() + " " +  &solution.feasible().to_string() + "\n"));
     }
 
     //let unique_best = best_solutions.into_iter().unique().collect();
    // println!("{:?}", unique_best);
 
-    parsing::write_solution_to_file( &sol_string);
-    parsing::pretty_print_solution_to_file(&helper::gen_solution_string(&b.chromosome(), &data));
-    let elapsed = now.elapsed();
+    if let Err(e) = parsing::write_solution_to_file(&sol_string)
+    {
+        eprintln!("{}", e);
+    }
+    let mut detail = helper::gen_solution_string(b.chromosome(), data, b_fitness);
+    if let Some(uncertainty) = &data.uncertainty
+    {
+        let (routes, _) = helper::split_unassigned(b.chromosome(), data);
+        detail.push('\n');
+        detail.push_str(&robustness::evaluate(&routes, data, uncertainty).to_string());
+    }
+    if let Err(e) = parsing::pretty_print_solution_to_file(&detail)
+    {
+        eprintln!("{}", e);
+    }
+    let elapsed = start.elapsed();
     println!("Elapsed: {:.2?}", elapsed);
 
+    let mut solution = solution::Solution::from_chromosome(b.chromosome(), data).with_fitness(b_fitness);
+    solution.seed = Some(seed);
+    solution.run_time_secs = elapsed.as_secs_f64();
+    if let Err(e) = parsing::write_solution_json(&solution)
+    {
+        eprintln!("{}", e);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2
+    {
+        eprintln!("Usage: {} <instance file> [seed] [--pareto] [--previous <solution file> [--continuity-cost <cost>]] [--objective <penalty|distance|lexicographic>] [--adaptive-penalty] [--plot] [--config <toml or json file>] [--<config-field> <value>]", args[0]);
+        eprintln!("       {} <instance file> --check <solution file>", args[0]);
+        eprintln!("       {} <instance file> [seed] --reoptimize <solution.json> --clock <time> [--add <ids>] [--cancel <ids>]", args[0]);
+        std::process::exit(1);
+    }
+    let filepath = &args[1];
+    let mut data = match parsing::parse_instance(filepath) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Unable to load instance {}: {}", filepath, e);
+            std::process::exit(1);
+        }
+    };
+
+    if args.len() > 3 && args[2] == "--check"
+    {
+        check_solution_file(&args[3], &data);
+        return;
+    }
+
+    let diagnostics = data.validate();
+    for diagnostic in diagnostics.iter()
+    {
+        eprintln!("{}", diagnostic);
+    }
+    if diagnostics.iter().any(|d| d.is_error())
+    {
+        eprintln!("Instance {} can not be solved without violations, skipping", filepath);
+        std::process::exit(1);
+    }
+
+    let pareto = args.iter().skip(2).any(|a| a == "--pareto");
+    let config = ga_config(&args);
+    if let Some(previous) = flag_value(&args, "--previous")
+    {
+        set_previous_solution(&mut data, previous, config.continuity_cost);
+    }
+    let seed: u64 = match args.get(2).filter(|a| !a.starts_with("--")) {
+        Some(s) => s.parse().unwrap_or_else(|_| {
+            eprintln!("Seed must be an unsigned integer, got {}", s);
+            std::process::exit(1);
+        }),
+        None => thread_rng().gen(),
+    };
+    println!("Seed: {}", seed);
+
 
-    //Note that this only works on Windows. Plotting solution and instance using Python
-    let output = Command::new("cmd").args(["/K", "python", PYTHON_SCRIPT_PATH]).output().expect("Failed!!");
-    //println!("{:?}", String::from_utf8(output.stdout))
+    let now = Instant::now();
+    if data.planning_days() > 1
+    {
+        run_week(&data, seed, &config, now);
+    }
+    else if let Some(solution_path) = flag_value(&args, "--reoptimize")
+    {
+        run_reoptimize(&data, solution_path, &args, seed, &config, now);
+    }
+    else if pareto
+    {
+        let populations = run_islands::<MultiObjectiveRoute, _, _>(&data, seed, &config, selection::NsgaIISurvivorSelection::new, selection::CrowdedTournamentSelection::new);
+        write_solutions(&populations, &data, &config, seed, now);
+
+        let all: Vec<MultiObjectiveRoute> = populations.into_iter().flatten().collect();
+        let reference = reference_fitness(&data, &config);
+        let mut front: Vec<&MultiObjectiveRoute> = Vec::new();
+        for &i in selection::non_dominated_sort(&all)[0].iter()
+        {
+            if !front.iter().any(|f| f.chromosome().genes == all[i].chromosome().genes)
+            {
+                front.push(&all[i]);
+            }
+        }
+        let front = solution::ParetoFront {
+            instance_name: data.instance_name.clone(),
+            seed: Some(seed),
+            run_time_secs: now.elapsed().as_secs_f64(),
+            solutions: front.iter()
+                .map(|i| solution::Solution::from_chromosome(i.chromosome(), &data).with_fitness(reference.calculate_fitness(i.chromosome()).0))
+                .collect(),
+        };
+        println!("Pareto front: {} solutions", front.solutions.len());
+        if let Err(e) = parsing::write_pareto_json(&front)
+        {
+            eprintln!("{}", e);
+        }
+    }
+    else
+    {
+        let populations = run_islands::<Route, _, _>(&data, seed, &config, selection::ElitismSurvivorSelection::new, selection::TournamentParentSelection::new);
+        write_solutions(&populations, &data, &config, seed, now);
+    }
+
+    if args.iter().any(|a| a == "--plot")
+    {
+        if let Err(e) = plot_solution()
+        {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// Plots the written solution and instance with the Python plotter. Note that this only works on Windows
+fn plot_solution() -> Result<(), IoError>
+{
+    let command = format!("cmd /C python {}", PYTHON_SCRIPT_PATH);
+    let failed = |source| IoError::Command { command: command.clone(), source };
+    if !cfg!(windows)
+    {
+        return Err(failed(std::io::Error::new(std::io::ErrorKind::Unsupported, "plotting needs Windows")));
+    }
+    let status = Command::new("cmd").args(["/C", "python", PYTHON_SCRIPT_PATH]).status().map_err(failed)?;
+    if !status.success()
+    {
+        return Err(failed(std::io::Error::other(format!("exited with {}", status))));
+    }
+    Ok(())
 }
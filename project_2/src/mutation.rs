@@ -1,180 +1,286 @@
 use std::cmp::{max, min};
-use std::ops::Index;
+use std::ops::{Index, Range};
 use rand::{Rng, RngCore};
 use rand::distributions::Uniform;
 use rand::distributions::Distribution;
 use rand::seq::SliceRandom;
 use crate::helper;
+use crate::parsing::Qualifications;
 
 use crate::individual::chromosome::Chromosome;
 
 pub trait Mutation {
-    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome);
+    /// Returns the nurse routes the mutation changed
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched;
     fn adjust_chance(&mut self, chance: f64);
 }
 
-mod local_search {
-    use super::*;
+/// Nurse routes changed by an operator, by index in the chromosome.
+/// Evaluations cached for the other routes stay valid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Touched {
+    Unchanged,
+    Routes(Vec<usize>),
+    All,
+}
 
-    pub fn in_invert(rng: &mut dyn RngCore, child: &mut Chromosome)
-    {
-        let mut split = helper::split_into_nurses(&child);
-        let nurse = Uniform::from(0..split.len()).sample(rng);
-        let _ = &split[nurse].reverse();
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
-        {
-            *gene = *cool_gene;
+impl Touched {
+    /// Routes that differ between two chromosomes, All if they have different numbers of routes
+    pub fn between(before: &Chromosome, after: &Chromosome) -> Self {
+        let before_routes: Vec<&[u16]> = before.genes.split(|&gene| gene == 0).collect();
+        let after_routes: Vec<&[u16]> = after.genes.split(|&gene| gene == 0).collect();
+        if before_routes.len() != after_routes.len() {
+            return Touched::All;
+        }
+        let changed: Vec<usize> = before_routes.iter().zip(after_routes.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(route, _)| route)
+            .collect();
+        if changed.is_empty() { Touched::Unchanged } else { Touched::Routes(changed) }
+    }
+
+    pub fn merge(self, other: Touched) -> Self {
+        match (self, other) {
+            (Touched::All, _) | (_, Touched::All) => Touched::All,
+            (Touched::Unchanged, touched) | (touched, Touched::Unchanged) => touched,
+            (Touched::Routes(mut routes), Touched::Routes(other)) => {
+                for route in other {
+                    if !routes.contains(&route) {
+                        routes.push(route);
+                    }
+                }
+                Touched::Routes(routes)
+            }
         }
     }
 
-    pub fn in_scramble(rng: &mut dyn RngCore, child: &mut Chromosome)
-    {
-        let mut split = helper::split_into_nurses(&child);
-        let nurse = Uniform::from(0..split.len()).sample(rng);
-        let _ = &split[nurse].shuffle(rng);
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
-        {
-            *gene = *cool_gene;
+    pub fn contains(&self, route: usize) -> bool {
+        match self {
+            Touched::Unchanged => false,
+            Touched::Routes(routes) => routes.contains(&route),
+            Touched::All => true,
         }
     }
+}
 
-    pub(crate) fn in_swap(rng: &mut dyn RngCore, child: &mut Chromosome)
+/// Operators work on the routes in place, `helper::route_ranges` gives where each route is in the chromosome
+mod local_search {
+    use super::*;
+
+    /// Number of routes with patients. Operators leave chromosomes with too few unchanged, days of a week plan can be nearly empty
+    fn non_empty_routes(routes: &[Range<usize>]) -> usize
     {
-        let mut split = helper::split_into_nurses(&child);
-        let mut nurse = Uniform::from(0..split.len()).sample(rng);
+        routes.iter().filter(|r| !r.is_empty()).count()
+    }
 
-        while split[nurse].is_empty()
-        {
-            nurse = (nurse+1) % split.len();
-        }
-        let gene_1 = rng.gen_range(0..split[nurse].len());
-        let gene_2 = rng.gen_range(0..split[nurse].len());
-        let _ = &split[nurse].swap(gene_1, gene_2);
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
+    fn non_empty_route(routes: &[Range<usize>], mut nurse: usize) -> usize
+    {
+        while routes[nurse].is_empty()
         {
-            *gene = *cool_gene;
+            nurse = (nurse+1) % routes.len();
         }
+        nurse
     }
 
-    pub fn in_insert(rng: &mut dyn RngCore, child: &mut Chromosome)
+    /// Route ranges, without the unassigned pool at the end if `pool`. Only the pool operators move patients in or out of it
+    fn nurse_routes(child: &Chromosome, pool: bool) -> Vec<Range<usize>>
     {
-        let mut split = helper::split_into_nurses(&child);
-        let mut nurse = Uniform::from(0..split.len()).sample(rng);
-        // TODO: fiks de dumme greiene her Axel
-        while split[nurse].is_empty()
-        {
-            nurse = (nurse+1) % split.len();
+        let mut routes = helper::route_ranges(child);
+        if pool {
+            routes.pop();
         }
-        let index = rng.gen_range(0..split[nurse].len());
-        let gene = split[nurse].remove(index);
+        routes
+    }
 
-        let new_index = if split[nurse].is_empty() {0} else {rng.gen_range(0..split[nurse].len())};
-        let _ = &split[nurse].insert(new_index, gene);
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
-        {
-            *gene = *cool_gene;
+    /// Moves the gene at `from` so it ends up before the gene now at `to`, shifting the genes between
+    fn move_gene(child: &mut Chromosome, from: usize, to: usize)
+    {
+        if to > from {
+            child.genes[from..to].rotate_left(1);
+        } else {
+            child.genes[to..=from].rotate_right(1);
         }
     }
 
-    ///Need two routes that contain patients
-    pub fn cross_swap(rng: &mut dyn RngCore, child: &mut Chromosome)
+    pub fn in_invert(rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched
     {
-        let mut split = helper::split_into_nurses(&child);
-        let between =  Uniform::from(0..split.len());
-        let mut nurse_1 = between.sample(rng);
-        let mut nurse_2 = between.sample(rng);
+        let routes = helper::route_ranges(child);
+        let nurse = Uniform::from(0..routes.len()).sample(rng);
+        child.genes[routes[nurse].clone()].reverse();
+        Touched::Routes(vec![nurse])
+    }
 
-        // TODO: fiks de dumme greiene her Axel
-        while split[nurse_1].is_empty()
-        {
-            nurse_1 = (nurse_1+1) % split.len();
-        }
+    pub fn in_scramble(rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched
+    {
+        let routes = helper::route_ranges(child);
+        let nurse = Uniform::from(0..routes.len()).sample(rng);
+        child.genes[routes[nurse].clone()].shuffle(rng);
+        Touched::Routes(vec![nurse])
+    }
 
-        while split[nurse_2].is_empty() || (nurse_1 == nurse_2)
-        {
-            nurse_2 = (nurse_2+1) % split.len();
+    pub(crate) fn in_swap(rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched
+    {
+        let routes = helper::route_ranges(child);
+        if non_empty_routes(&routes) == 0 {
+            return Touched::Unchanged;
         }
+        let nurse = non_empty_route(&routes, Uniform::from(0..routes.len()).sample(rng));
+        let route = routes[nurse].clone();
 
-        let nurse_1_index =  rng.gen_range(0..split[nurse_1].len());
-        let nurse_2_index =  rng.gen_range(0..split[nurse_2].len());
-
-        let nurse_1_gene = split[nurse_1].remove(nurse_1_index);
-        let nurse_2_gene = split[nurse_2].remove(nurse_2_index);
+        let gene_1 = rng.gen_range(0..route.len());
+        let gene_2 = rng.gen_range(0..route.len());
+        child.genes[route].swap(gene_1, gene_2);
+        Touched::Routes(vec![nurse])
+    }
 
-        let _ = &split[nurse_2].insert(nurse_2_index, nurse_1_gene);
-        let _ = &split[nurse_1].insert(nurse_1_index, nurse_2_gene);
+    pub fn in_insert(rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched
+    {
+        let routes = helper::route_ranges(child);
+        if non_empty_routes(&routes) == 0 {
+            return Touched::Unchanged;
+        }
+        let nurse = non_empty_route(&routes, Uniform::from(0..routes.len()).sample(rng));
+        let route = routes[nurse].clone();
 
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
-        {
-            *gene = *cool_gene;
+        // Index after removing the gene, the gene ends up at new_index
+        let index = rng.gen_range(0..route.len());
+        let new_index = if route.len() == 1 {0} else {rng.gen_range(0..route.len() - 1)};
+        let (from, to) = (route.start + index, route.start + new_index);
+        if to > from {
+            move_gene(child, from, to + 1);
+        } else {
+            move_gene(child, from, to);
         }
+        Touched::Routes(vec![nurse])
     }
 
-    //TODO
-    pub fn cross_swap_subset(rng: &mut dyn RngCore, child: &mut Chromosome)
+    ///Need two routes that contain patients
+    pub fn cross_swap(rng: &mut dyn RngCore, child: &mut Chromosome, pool: bool) -> Touched
     {
-        let mut split = helper::split_into_nurses(&child);
-        let between =  Uniform::from(0..split.len());
-        let mut nurse_1 = between.sample(rng);
-        let mut nurse_2 = between.sample(rng);
-
-        // TODO: fiks de dumme greiene her Axel
-        while split[nurse_1].is_empty()
-        {
-            nurse_1 = (nurse_1+1) % split.len();
+        let routes = nurse_routes(child, pool);
+        if non_empty_routes(&routes) < 2 {
+            return Touched::Unchanged;
         }
+        let between =  Uniform::from(0..routes.len());
+        let nurse_1 = between.sample(rng);
+        let mut nurse_2 = between.sample(rng);
 
-        while split[nurse_2].is_empty() || (nurse_1 == nurse_2)
+        let nurse_1 = non_empty_route(&routes, nurse_1);
+        while routes[nurse_2].is_empty() || (nurse_1 == nurse_2)
         {
-            nurse_2 = (nurse_2+1) % split.len();
+            nurse_2 = (nurse_2+1) % routes.len();
         }
 
-        let nurse_1_index =  rng.gen_range(0..split[nurse_1].len());
-        let nurse_2_index =  rng.gen_range(0..split[nurse_2].len());
-
-        let nurse_1_gene = split[nurse_1].remove(nurse_1_index);
-        //min(t, t2)..max(t, t2)
-       // let t = split[nurse_1].drain()
-        let nurse_2_gene = split[nurse_2].remove(nurse_2_index);
+        let nurse_1_index = routes[nurse_1].start + rng.gen_range(0..routes[nurse_1].len());
+        let nurse_2_index = routes[nurse_2].start + rng.gen_range(0..routes[nurse_2].len());
+        child.genes.swap(nurse_1_index, nurse_2_index);
+        Touched::Routes(vec![nurse_1, nurse_2])
+    }
 
-        let _ = &split[nurse_2].insert(nurse_2_index, nurse_1_gene);
-        let _ = &split[nurse_1].insert(nurse_1_index, nurse_2_gene);
+    //TODO: swap subsets instead of single genes
+    pub fn cross_swap_subset(rng: &mut dyn RngCore, child: &mut Chromosome, pool: bool) -> Touched
+    {
+        cross_swap(rng, child, pool)
+    }
 
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
-        {
-            *gene = *cool_gene;
+    /// Like `cross_swap`, but only swaps patients both nurses are qualified for.
+    /// Gives up after a few tries, some pairs of routes have nothing to swap
+    pub fn cross_swap_qualified(rng: &mut dyn RngCore, child: &mut Chromosome, qualifications: &Qualifications, pool: bool) -> Touched
+    {
+        let routes = nurse_routes(child, pool);
+        if non_empty_routes(&routes) == 0 {
+            return Touched::Unchanged;
+        }
+        let between =  Uniform::from(0..routes.len());
+        for _ in 0..QUALIFIED_TRIES {
+            let nurse_1 = non_empty_route(&routes, between.sample(rng));
+            let nurse_2 = non_empty_route(&routes, between.sample(rng));
+            if nurse_1 == nurse_2 {
+                continue;
+            }
+            let nurse_1_index = routes[nurse_1].start + rng.gen_range(0..routes[nurse_1].len());
+            let nurse_2_index = routes[nurse_2].start + rng.gen_range(0..routes[nurse_2].len());
+            if qualifications.allows(nurse_2, child.genes[nurse_1_index]) && qualifications.allows(nurse_1, child.genes[nurse_2_index]) {
+                child.genes.swap(nurse_1_index, nurse_2_index);
+                return Touched::Routes(vec![nurse_1, nurse_2]);
+            }
         }
+        Touched::Unchanged
     }
+
     ///Need at least one route that contain patients
-    pub fn cross_insert(rng: &mut dyn RngCore, child: &mut Chromosome)
+    pub fn cross_insert(rng: &mut dyn RngCore, child: &mut Chromosome, pool: bool) -> Touched
     {
-        let mut split = helper::split_into_nurses(&child);
-        let mut gene: u16 = 0;
-
-        {
-            let mut t = rng.gen_range(0..split.len());
+        let routes = nurse_routes(child, pool);
+        if non_empty_routes(&routes) == 0 {
+            return Touched::Unchanged;
+        }
+        let nurse_1 = non_empty_route(&routes, rng.gen_range(0..routes.len()));
+        let from = routes[nurse_1].start + rng.gen_range(0..routes[nurse_1].len());
+        let nurse_2 = rng.gen_range(0..routes.len());
+        insert_into(rng, child, &routes, (nurse_1, from), nurse_2)
+    }
 
-            //TODO: sikkert en MYE bedre måte å gjøre dette på
-            while split[t].is_empty()
-            {
-                t = (t+1) % split.len();
-            }
-            let route_1 = &mut split[t];
+    /// Like `cross_insert`, but the patient only moves to a nurse qualified for it, or within its route if there is none
+    pub fn cross_insert_qualified(rng: &mut dyn RngCore, child: &mut Chromosome, qualifications: &Qualifications, pool: bool) -> Touched
+    {
+        let routes = nurse_routes(child, pool);
+        if non_empty_routes(&routes) == 0 {
+            return Touched::Unchanged;
+        }
+        let nurse_1 = non_empty_route(&routes, rng.gen_range(0..routes.len()));
+        let from = routes[nurse_1].start + rng.gen_range(0..routes[nurse_1].len());
+        let qualified: Vec<usize> = qualifications.qualified_nurses(child.genes[from]).filter(|&n| n < routes.len()).collect();
+        let nurse_2 = *qualified.choose(rng).unwrap_or(&nurse_1);
+        insert_into(rng, child, &routes, (nurse_1, from), nurse_2)
+    }
 
-            let old_index = rng.gen_range(0..route_1.len());
-            gene = route_1.remove(old_index);
+    /// Moves the gene at `from` in route `nurse_1` to a random position of route `nurse_2`, the end included
+    fn insert_into(rng: &mut dyn RngCore, child: &mut Chromosome, routes: &[Range<usize>], (nurse_1, from): (usize, usize), nurse_2: usize) -> Touched
+    {
+        if nurse_2 == nurse_1 {
+            // Index after removing the gene
+            let new_index = rng.gen_range(0..routes[nurse_1].len());
+            let to = routes[nurse_1].start + new_index;
+            move_gene(child, from, if to >= from { to + 1 } else { to });
+        } else {
+            let route_2 = routes[nurse_2].clone();
+            let new_index = rng.gen_range(0..=route_2.len());
+            move_gene(child, from, route_2.start + new_index);
         }
+        Touched::Routes(vec![nurse_1, nurse_2])
+    }
 
-        let route_2 = split.choose_mut(rng).unwrap();
-        let new_index = if route_2.is_empty() {0} else {rng.gen_range(0..route_2.len())};
-        let _ = route_2.insert(new_index, gene);
+    const QUALIFIED_TRIES: usize = 10;
 
-        for (gene, cool_gene) in child.iter_mut().zip(helper::combine_into_chromo(&split).iter())
-        {
-            *gene = *cool_gene;
+    /// Moves a random patient with a drop cost from a nurse route to the unassigned pool, the last route
+    pub fn remove_to_pool(rng: &mut dyn RngCore, child: &mut Chromosome, optional: &[bool]) -> Touched
+    {
+        let routes = helper::route_ranges(child);
+        let pool = routes.len() - 1;
+        let candidates: Vec<usize> = (0..routes[pool].start).filter(|&i| optional[child.genes[i] as usize]).collect();
+        match candidates.choose(rng) {
+            Some(&from) => {
+                let nurse = routes.iter().position(|r| r.contains(&from)).unwrap_or(pool);
+                insert_into(rng, child, &routes, (nurse, from), pool)
+            }
+            None => Touched::Unchanged,
         }
     }
 
+    /// Moves a random patient from the unassigned pool to a random position of a nurse route
+    pub fn insert_from_pool(rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched
+    {
+        let routes = helper::route_ranges(child);
+        let pool = routes.len() - 1;
+        if routes[pool].is_empty() || pool == 0 {
+            return Touched::Unchanged;
+        }
+        let from = routes[pool].start + rng.gen_range(0..routes[pool].len());
+        let nurse = rng.gen_range(0..pool);
+        insert_into(rng, child, &routes, (pool, from), nurse)
+    }
 }
 
 pub(crate) mod in_route {
@@ -184,14 +290,16 @@ pub(crate) mod in_route {
     }
     impl InRouteInversionMutation {
         pub fn new(chance: f64) -> Self {
-            assert!(0.0 <= chance  && chance <= 1.0);
+            assert!((0.0..=1.0).contains(&chance));
             Self {chance}
         }
     }
     impl Mutation for InRouteInversionMutation {
-        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
             if rng.gen_bool(self.chance as _) {
-                local_search::in_invert(rng, child);
+                local_search::in_invert(rng, child)
+            } else {
+                Touched::Unchanged
             }
         }
 
@@ -205,14 +313,16 @@ pub(crate) mod in_route {
     }
     impl InRouteScrambleMutation {
         pub fn new(chance: f64) -> Self {
-            assert!(0.0 <= chance  && chance <= 1.0);
+            assert!((0.0..=1.0).contains(&chance));
             Self {chance}
         }
     }
     impl Mutation for InRouteScrambleMutation {
-        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
             if rng.gen_bool(self.chance as _) {
-                local_search::in_scramble(rng, child);
+                local_search::in_scramble(rng, child)
+            } else {
+                Touched::Unchanged
             }
         }
 
@@ -226,14 +336,16 @@ pub(crate) mod in_route {
     }
     impl InRouteSwapMutation {
         pub fn new(chance: f64) -> Self {
-            assert!(0.0 <= chance  && chance <= 1.0);
+            assert!((0.0..=1.0).contains(&chance));
             Self {chance}
         }
     }
     impl Mutation for InRouteSwapMutation {
-        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
             if rng.gen_bool(self.chance as _) {
-                local_search::in_swap(rng, child);
+                local_search::in_swap(rng, child)
+            } else {
+                Touched::Unchanged
             }
         }
 
@@ -247,14 +359,16 @@ pub(crate) mod in_route {
     }
     impl InRouteInsertMutation {
         pub fn new(chance: f64) -> Self {
-            assert!(0.0 <= chance  && chance <= 1.0);
+            assert!((0.0..=1.0).contains(&chance));
             Self {chance}
         }
     }
     impl Mutation for InRouteInsertMutation {
-        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
             if rng.gen_bool(self.chance as _) {
-                local_search::in_insert(rng, child);
+                local_search::in_insert(rng, child)
+            } else {
+                Touched::Unchanged
             }
         }
 
@@ -270,17 +384,36 @@ pub mod cross_route {
 
     pub struct CrossRouteSwapMutation {
         chance: f64,
+        /// Set when patients need skills, mutants then only give patients to qualified nurses
+        qualifications: Option<Qualifications>,
+        /// Chromosomes end with the unassigned pool, which is left to the pool operators
+        pool: bool,
     }
     impl CrossRouteSwapMutation {
         pub fn new(chance: f64) -> Self {
-            assert!(0.0 <= chance  && chance <= 1.0);
-            Self {chance}
+            assert!((0.0..=1.0).contains(&chance));
+            Self {chance, qualifications: None, pool: false}
+        }
+
+        pub fn with_qualifications(chance: f64, qualifications: Qualifications) -> Self {
+            Self {qualifications: Some(qualifications), ..Self::new(chance)}
+        }
+
+        /// Leaves the unassigned pool at the end of the chromosome alone
+        pub fn excluding_pool(mut self) -> Self {
+            self.pool = true;
+            self
         }
     }
     impl Mutation for CrossRouteSwapMutation {
-        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
             if rng.gen_bool(self.chance as _) {
-                local_search::cross_swap(rng, child);
+                match &self.qualifications {
+                    Some(qualifications) => local_search::cross_swap_qualified(rng, child, qualifications, self.pool),
+                    None => local_search::cross_swap(rng, child, self.pool),
+                }
+            } else {
+                Touched::Unchanged
             }
         }
 
@@ -291,17 +424,90 @@ pub mod cross_route {
 
     pub struct CrossRouteInsertMutation {
         chance: f64,
+        /// Set when patients need skills, mutants then only give patients to qualified nurses
+        qualifications: Option<Qualifications>,
+        /// Chromosomes end with the unassigned pool, which is left to the pool operators
+        pool: bool,
     }
     impl CrossRouteInsertMutation {
         pub fn new(chance: f64) -> Self {
-            assert!(0.0 <= chance  && chance <= 1.0);
-            Self {chance}
+            assert!((0.0..=1.0).contains(&chance));
+            Self {chance, qualifications: None, pool: false}
+        }
+
+        pub fn with_qualifications(chance: f64, qualifications: Qualifications) -> Self {
+            Self {qualifications: Some(qualifications), ..Self::new(chance)}
+        }
+
+        /// Leaves the unassigned pool at the end of the chromosome alone
+        pub fn excluding_pool(mut self) -> Self {
+            self.pool = true;
+            self
         }
     }
     impl Mutation for CrossRouteInsertMutation {
-        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
+            if rng.gen_bool(self.chance as _) {
+                match &self.qualifications {
+                    Some(qualifications) => local_search::cross_insert_qualified(rng, child, qualifications, self.pool),
+                    None => local_search::cross_insert(rng, child, self.pool),
+                }
+            } else {
+                Touched::Unchanged
+            }
+        }
+
+        fn adjust_chance(&mut self, chance: f64) {
+            self.chance = chance;
+        }
+    }
+
+}
+
+/// Operators for instances with an unassigned pool after the nurse routes
+pub mod pool {
+    use super::*;
+
+    pub struct PoolRemoveMutation {
+        chance: f64,
+        /// Patients with a drop cost, indexed by gene
+        optional: Vec<bool>,
+    }
+    impl PoolRemoveMutation {
+        pub fn new(chance: f64, optional: Vec<bool>) -> Self {
+            assert!((0.0..=1.0).contains(&chance));
+            Self {chance, optional}
+        }
+    }
+    impl Mutation for PoolRemoveMutation {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
             if rng.gen_bool(self.chance as _) {
-                local_search::cross_insert(rng, child);
+                local_search::remove_to_pool(rng, child, &self.optional)
+            } else {
+                Touched::Unchanged
+            }
+        }
+
+        fn adjust_chance(&mut self, chance: f64) {
+            self.chance = chance;
+        }
+    }
+
+    pub struct PoolInsertMutation {
+        chance: f64,
+    }
+    impl PoolInsertMutation {
+        pub fn new(chance: f64) -> Self {
+            assert!((0.0..=1.0).contains(&chance));
+            Self {chance}
+        }
+    }
+    impl Mutation for PoolInsertMutation {
+        fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
+            if rng.gen_bool(self.chance as _) {
+                local_search::insert_from_pool(rng, child)
+            } else {
+                Touched::Unchanged
             }
         }
 
@@ -317,12 +523,12 @@ pub struct InversionMutation {
 }
 impl InversionMutation {
     pub fn new(chance: f64) -> Self {
-        assert!(0.0 <= chance  && chance <= 1.0);
+        assert!((0.0..=1.0).contains(&chance));
         Self {chance}
     }
 }
 impl Mutation for InversionMutation {
-    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
 
         if rng.gen_bool(self.chance as _) {
             let between = Uniform::from(0..child.len());
@@ -331,7 +537,9 @@ impl Mutation for InversionMutation {
             //println!("{:?}", &child.genes[min(t, t2)..max(t, t2)]);
              let _ = &child.genes[min(t, t2)..max(t, t2)].reverse();
             //println!("{:?}", &child.genes[min(t, t2)..max(t, t2)]);
-
+            Touched::All
+        } else {
+            Touched::Unchanged
         }
     }
 
@@ -345,12 +553,12 @@ pub struct ScrambleMutation {
 }
 impl ScrambleMutation {
     pub fn new(chance: f64) -> Self {
-        assert!(0.0 <= chance  && chance <= 1.0);
+        assert!((0.0..=1.0).contains(&chance));
         Self {chance}
     }
 }
 impl Mutation for ScrambleMutation {
-    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
         let between = Uniform::from(0..child.len());
 
         if rng.gen_bool(self.chance as _) {
@@ -361,7 +569,9 @@ impl Mutation for ScrambleMutation {
             //println!("{:?}", &child.genes[min(t, t2)..max(t, t2)]);
             let _ = &child.genes[min(t, t2)..max(t, t2)].shuffle(rng);
             //println!("{:?}", &child.genes[min(t, t2)..max(t, t2)]);
-
+            Touched::All
+        } else {
+            Touched::Unchanged
         }
     }
 
@@ -375,19 +585,18 @@ pub struct SwapMutation {
 }
 impl SwapMutation {
     pub fn new(chance: f64) -> Self {
-        assert!(0.0 <= chance  && chance <= 1.0);
+        assert!((0.0..=1.0).contains(&chance));
         Self {chance}
     }
 }
 impl Mutation for SwapMutation {
-    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
-        let mut pos1 = 0u16;
-        for gene in child.iter_mut() {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) -> Touched {
+        for _gene in child.iter_mut() {
             if rng.gen_bool(self.chance as _) {
-                pos1 = *gene;
                 todo!()
             }
         }
+        Touched::Unchanged
     }
 
     fn adjust_chance(&mut self, chance: f64) {
@@ -1,24 +1,196 @@
 use std::collections::HashMap;
 use std::fs;
 use serde::{Deserialize};
+use rand::{Rng, RngCore};
+use crate::error::{IoError, ParseError};
+use crate::solution::{ParetoFront, RoutePlan, Solution, WeekSolution};
 
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct TrainData {
     pub instance_name: String,
+    /// May be left out when `nurses` is given
+    #[serde(default)]
     pub nbr_nurses: i32,
+    /// Capacity of every nurse when `nurses` is not given
+    #[serde(default)]
     pub capacity_nurse: i32,
+    /// Best known objective, 0 when unknown
     pub benchmark: f32,
     pub depot: Depot,
     pub patients: HashMap<String, Patient>,
-    pub travel_times: Vec<Vec<f64>>
+    /// Depot and patients 1..=n, optionally followed by extra nodes nurses start or end at.
+    /// Built from the coordinates with `distance_metric` when left out
+    #[serde(default)]
+    pub travel_times: Vec<Vec<f64>>,
+    #[serde(default)]
+    pub distance_metric: DistanceMetric,
+    /// Heterogeneous fleet. Without it every nurse has `capacity_nurse` and works from the depot between 0 and its return time
+    #[serde(default)]
+    pub nurses: Option<Vec<Nurse>>,
+    /// Length of the planning horizon in days, single-day instances leave it out
+    #[serde(default)]
+    pub nbr_days: u32,
+    /// Pairs of visits two nurses have to start at the same time, e.g. lifting a patient
+    #[serde(default)]
+    pub synchronized: Vec<[u16; 2]>,
+    /// Pairs of visits that must start in order, within a gap
+    #[serde(default)]
+    pub precedences: Vec<Precedence>,
+    /// Break every nurse without its own `break_rule` takes
+    #[serde(default)]
+    pub break_rule: Option<BreakRule>,
+    /// Working time limit of every nurse without its own `max_working_time`
+    #[serde(default)]
+    pub max_working_time: Option<f64>,
+    /// Driving speed over the day. `travel_times` are then the minutes at speed 1, see [`TrainData::travel_time`]
+    #[serde(default)]
+    pub speed_profile: Vec<SpeedSlot>,
+    /// How much travel and care times vary, for the Monte Carlo evaluation in [`crate::robustness`]
+    #[serde(default)]
+    pub uncertainty: Option<Uncertainty>,
 
+    /// Patients indexed by gene - 1, built once after loading by [`TrainData::index_patients`]
+    #[serde(skip)]
+    patient_table: Vec<Patient>,
+    /// One entry per nurse, from `nurses` or the single depot
+    #[serde(skip)]
+    nurse_table: Vec<Nurse>,
+    /// Skills of `nurse_table` and `patient_table` as bit sets
+    #[serde(skip)]
+    qualifications: Qualifications,
+    /// Nurse of every patient on a previous planning day, see [`TrainData::set_previous_routes`]
+    #[serde(skip)]
+    continuity: Option<Continuity>,
+    /// Allowed day patterns of every patient as bit sets of days, indexed by gene - 1
+    #[serde(skip)]
+    pattern_table: Vec<Vec<u64>>,
+    /// Some patient has a drop cost
+    #[serde(skip)]
+    optional_patients: bool,
 }
+
+/// A nurse's capacity, working hours and where the route starts and ends.
+/// Locations are nodes of the travel matrix: 0 is the depot, nodes after the patients are homes or offices
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Nurse {
+    pub capacity: i32,
+    #[serde(default)]
+    pub shift_start: f64,
+    pub shift_end: f64,
+    #[serde(default)]
+    pub start_location: usize,
+    #[serde(default)]
+    pub end_location: usize,
+    /// Per minute from shift start until back at the end location, 0 for unused nurses
+    #[serde(default)]
+    pub cost_per_minute: f64,
+    /// Qualifications, e.g. "wound_care". A nurse may only visit patients whose skills are all in here
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// Break the nurse takes if still working when its window opens
+    #[serde(default)]
+    pub break_rule: Option<BreakRule>,
+    /// Longest the nurse works without a break: from shift start until the break, and from the break until back
+    #[serde(default)]
+    pub max_working_time: Option<f64>,
+}
+
+/// A break of `duration` minutes, taken entirely between `start` and `end`, e.g. 30 minutes between 11:00 and 13:00.
+/// The evaluator places it between the visits where it costs least
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct BreakRule {
+    pub duration: f64,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl BreakRule {
+    /// Latest the break can begin and still end within its window
+    pub fn latest_start(&self) -> f64 {
+        self.end - self.duration
+    }
+}
+
+/// Travel goes at `speed` times the speed of `travel_times` from `start` until the next slot starts
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SpeedSlot {
+    pub start: f64,
+    pub speed: f64,
+}
+
+/// Random durations around the nominal travel and care times, simulated `samples` times from `seed`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Uncertainty {
+    #[serde(default)]
+    pub travel: DurationNoise,
+    #[serde(default)]
+    pub care: DurationNoise,
+    #[serde(default = "default_samples")]
+    pub samples: usize,
+    #[serde(default)]
+    pub seed: u64,
+    /// Added to the fitness per expected late visit and per expected minute of overtime, 0 only reports them
+    #[serde(default)]
+    pub fitness_weight: f64,
+}
+
+fn default_samples() -> usize {
+    100
+}
+
+/// Distribution of a duration relative to its nominal value, with the nominal value as mean
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum DurationNoise {
+    #[default]
+    Fixed,
+    /// Nominal value times a lognormal factor whose logarithm has standard deviation `sigma`
+    LogNormal { sigma: f64 },
+    /// Uniform within `spread` times the nominal value on both sides
+    Uniform { spread: f64 },
+}
+
+impl DurationNoise {
+    pub fn sample(&self, rng: &mut dyn RngCore, nominal: f64) -> f64 {
+        match *self {
+            DurationNoise::Fixed => nominal,
+            DurationNoise::LogNormal { sigma } => {
+                // Box-Muller, shifted so the factor has mean 1
+                let (u, v): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+                let z = (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos();
+                nominal * (sigma * z - sigma * sigma / 2.0).exp()
+            }
+            DurationNoise::Uniform { spread } => nominal * (1.0 + spread * (2.0 * rng.gen::<f64>() - 1.0)),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            DurationNoise::Fixed => true,
+            DurationNoise::LogNormal { sigma } => sigma >= 0.0 && sigma.is_finite(),
+            DurationNoise::Uniform { spread } => (0.0..=1.0).contains(&spread),
+        }
+    }
+}
+
+/// Visit `then` starts at least `min_gap` and at most `max_gap` minutes after visit `first` starts
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Precedence {
+    pub first: u16,
+    pub then: u16,
+    #[serde(default)]
+    pub min_gap: f64,
+    /// No upper limit when left out
+    #[serde(default)]
+    pub max_gap: Option<f64>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Depot {
     pub return_time: f64,
-    x_coord: i32,
-    y_coord: i32
+    pub x_coord: f64,
+    pub y_coord: f64,
 }
 #[derive(Debug, Clone, Deserialize)]
 pub struct Patient {
@@ -26,22 +198,735 @@ pub struct Patient {
     pub(crate) demand: i32,
     pub end_time: f64,
     pub start_time: f64,
-    pub x_coord: i32,
-    pub y_coord: i32,
+    pub x_coord: f64,
+    pub y_coord: f64,
+    #[serde(default)]
+    pub time_window: TimeWindowKind,
+    /// Per minute care starts before start_time, only for soft-both windows
+    #[serde(default)]
+    pub earliness_cost: f64,
+    /// Per minute care finishes after end_time, only for soft windows
+    #[serde(default)]
+    pub tardiness_cost: f64,
+    /// Skills the visiting nurse needs, none by default
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// Visits needed over the planning horizon, 1 unless `patterns` says otherwise
+    #[serde(default)]
+    pub frequency: Option<u32>,
+    /// Allowed combinations of days (0-based), e.g. [[0, 2, 4]] for Mon/Wed/Fri.
+    /// Empty allows any `frequency` different days
+    #[serde(default)]
+    pub patterns: Vec<Vec<u32>>,
+    /// Cost of leaving the patient unvisited. Patients without one have to be visited
+    #[serde(default)]
+    pub drop_cost: Option<f64>,
+}
+
+/// Which nurse visited each patient in a reference solution, usually the previous day's.
+/// Every patient that now gets another nurse costs `change_cost`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Continuity {
+    /// Indexed by gene, None for patients the reference did not visit
+    previous_nurse: Vec<Option<usize>>,
+    pub change_cost: f64,
+}
+
+impl Continuity {
+    /// Nurse that visited patient `gene` in the reference
+    #[inline]
+    pub fn previous_nurse(&self, gene: u16) -> Option<usize> {
+        self.previous_nurse.get(gene as usize).copied().flatten()
+    }
+
+    /// Cost of nurse `nurse` visiting patient `gene`
+    #[inline]
+    pub fn cost(&self, nurse: usize, gene: u16) -> f64 {
+        match self.previous_nurse(gene) {
+            Some(previous) if previous != nurse => self.change_cost,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Longest planning horizon, day patterns are stored as bits of a u64 and "any k days" is enumerated
+pub const MAX_DAYS: u32 = 14;
+
+/// Every set of `frequency` days out of `nbr_days`, as bit sets
+fn day_combinations(nbr_days: u32, frequency: u32) -> Vec<u64> {
+    (0u64..1 << nbr_days).filter(|days| days.count_ones() == frequency).collect()
+}
+
+impl Patient {
+    /// Allowed day patterns as bit sets, in the order of `patterns`
+    fn day_patterns(&self, nbr_days: u32) -> Vec<u64> {
+        if self.patterns.is_empty() {
+            day_combinations(nbr_days, self.frequency.unwrap_or(1))
+        } else {
+            self.patterns.iter().map(|days| days.iter().fold(0u64, |mask, &day| mask | 1 << day)).collect()
+        }
+    }
+}
+
+/// Most distinct skill names an instance can use, skills are stored as bits of a u64
+pub const MAX_SKILLS: usize = 64;
+
+/// Which nurse may visit which patient, with skills as bit sets so a check is a single mask.
+/// Small enough to be cloned into operators that have no access to the instance
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Qualifications {
+    nurse_skills: Vec<u64>,
+    /// Indexed by gene, gene 0 needs nothing
+    patient_skills: Vec<u64>,
+}
+
+impl Qualifications {
+    fn new(nurses: &[Nurse], patients: &[Patient]) -> Self {
+        let mut names: Vec<&String> = nurses.iter().flat_map(|n| n.skills.iter())
+            .chain(patients.iter().flat_map(|p| p.skills.iter()))
+            .collect();
+        names.sort();
+        names.dedup();
+        let mask = |skills: &Vec<String>| skills.iter()
+            .filter_map(|skill| names.binary_search(&skill).ok())
+            .fold(0u64, |mask, bit| mask | 1u64 << bit);
+        Self {
+            nurse_skills: nurses.iter().map(|n| mask(&n.skills)).collect(),
+            patient_skills: std::iter::once(0).chain(patients.iter().map(|p| mask(&p.skills))).collect(),
+        }
+    }
+
+    /// True if the nurse at index `nurse` has every skill patient `gene` needs.
+    /// Routes past the fleet, the unassigned pool, take any patient
+    #[inline]
+    pub fn allows(&self, nurse: usize, gene: u16) -> bool {
+        let needed = self.patient_skills[gene as usize];
+        self.nurse_skills.get(nurse).is_none_or(|&skills| needed & skills == needed)
+    }
+
+    /// False if no patient needs any skill, so every nurse may visit every patient
+    pub fn is_restricted(&self) -> bool {
+        self.patient_skills.iter().any(|&skills| skills != 0)
+    }
+
+    /// Nurses allowed to visit patient `gene`
+    pub fn qualified_nurses(&self, gene: u16) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nurse_skills.len()).filter(move |&nurse| self.allows(nurse, gene))
+    }
+}
+
+/// How strictly a patient's time window is enforced, hard unless the instance says otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeWindowKind {
+    /// Nurses wait until start_time, finishing care after end_time breaks a constraint
+    #[default]
+    Hard,
+    /// Nurses wait until start_time, finishing after end_time costs tardiness_cost per minute
+    SoftLate,
+    /// Care starts on arrival, before start_time at earliness_cost per minute, and may finish late as for soft-late
+    SoftBoth,
+}
+
+/// How travel times are computed from coordinates, for instances without a `travel_times` matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    #[default]
+    Euclidean,
+    /// Euclidean rounded down to one decimal, as some published Solomon results use.
+    /// [`parse_solomon`] keeps the exact distances
+    RoundedEuclidean,
+    Manhattan,
+    /// Great-circle kilometres, with x_coord the longitude and y_coord the latitude in degrees.
+    /// A `speed_profile` turns kilometres into minutes
+    Haversine,
+}
+
+/// Mean earth radius in kilometres
+const EARTH_RADIUS: f64 = 6371.0;
+
+impl DistanceMetric {
+    pub fn distance(&self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+        match self {
+            DistanceMetric::Euclidean => (x1 - x2).hypot(y1 - y2),
+            DistanceMetric::RoundedEuclidean => ((x1 - x2).hypot(y1 - y2) * 10.0).floor() / 10.0,
+            DistanceMetric::Manhattan => (x1 - x2).abs() + (y1 - y2).abs(),
+            DistanceMetric::Haversine => {
+                let (lat1, lat2) = (y1.to_radians(), y2.to_radians());
+                let h = ((lat2 - lat1) / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * ((x2 - x1).to_radians() / 2.0).sin().powi(2);
+                2.0 * EARTH_RADIUS * h.sqrt().asin()
+            }
+        }
+    }
+}
+
+impl Patient {
+    /// Start of care for a nurse arriving at `arrival`
+    pub fn care_start(&self, arrival: f64) -> f64 {
+        match self.time_window {
+            TimeWindowKind::SoftBoth => arrival,
+            _ => arrival.max(self.start_time),
+        }
+    }
+
+    pub fn has_hard_window(&self) -> bool {
+        self.time_window == TimeWindowKind::Hard
+    }
+
+    /// Earliness and tardiness cost of care from `start` to `departure`, always 0 for hard windows
+    pub fn window_cost(&self, start: f64, departure: f64) -> f64 {
+        if self.has_hard_window() {
+            return 0.0;
+        }
+        self.earliness_cost * (self.start_time - start).max(0.0) + self.tardiness_cost * (departure - self.end_time).max(0.0)
+    }
 }
 //TODO bruke bedre datatyper enn i32/f32 på alt
 
+impl TrainData {
+    /// Number of patients in the instance, patient ids are 1..=nbr_patients
+    pub fn nbr_patients(&self) -> usize {
+        self.patients.len()
+    }
+
+    /// The benchmark, None when the instance has none, e.g. a Solomon instance
+    pub fn known_benchmark(&self) -> Option<f32> {
+        Some(self.benchmark).filter(|&benchmark| benchmark > 0.0)
+    }
+
+    /// Minutes from node `from` to node `to` leaving at `depart`. Without a speed profile that is the matrix entry,
+    /// with one the distance is driven through the slots at their speed (Ichoua, Gendreau and Potvin),
+    /// so leaving later never arrives earlier. Speed is 1 before the first slot
+    #[inline]
+    pub fn travel_time(&self, from: usize, to: usize, depart: f64) -> f64 {
+        let distance = self.travel_times[from][to];
+        if self.speed_profile.is_empty() {
+            return distance;
+        }
+        let mut time = depart;
+        let mut remaining = distance;
+        let mut slot = self.speed_profile.partition_point(|s| s.start <= depart);
+        loop {
+            let speed = if slot == 0 { 1.0 } else { self.speed_profile[slot - 1].speed };
+            let until = self.speed_profile.get(slot).map_or(f64::INFINITY, |s| s.start);
+            if time + remaining / speed <= until {
+                return time + remaining / speed - depart;
+            }
+            remaining -= (until - time) * speed;
+            time = until;
+            slot += 1;
+        }
+    }
+
+    /// True if travel times depend on the time of day
+    pub fn is_time_dependent(&self) -> bool {
+        !self.speed_profile.is_empty()
+    }
+
+    /// Longest a trip can take relative to its `travel_times` entry
+    pub fn slowest_factor(&self) -> f64 {
+        self.speed_profile.iter().map(|s| 1.0 / s.speed).fold(1.0, f64::max)
+    }
+
+    /// Coordinates of the depot (node 0) or a patient, None for extra nodes
+    pub fn coordinates(&self, node: usize) -> Option<(f64, f64)> {
+        if node == 0 {
+            return Some((self.depot.x_coord, self.depot.y_coord));
+        }
+        self.patients.get(&node.to_string()).map(|p| (p.x_coord, p.y_coord))
+    }
+
+    /// Travel times between the depot and patients 1..=n from their coordinates with `distance_metric`
+    pub fn distance_matrix(&self) -> Vec<Vec<f64>> {
+        let points: Vec<(f64, f64)> = (0..=self.nbr_patients()).filter_map(|node| self.coordinates(node)).collect();
+        points.iter().map(|&from| points.iter().map(|&to| self.distance_metric.distance(from, to)).collect()).collect()
+    }
+
+    /// Patient visited by a (non-zero) gene, without allocating or hashing
+    #[inline]
+    pub fn patient(&self, gene: u16) -> &Patient {
+        &self.patient_table[gene as usize - 1]
+    }
+
+    /// Patient with the given instance key ("1".."n")
+    pub fn patient_by_id(&self, id: &str) -> Option<&Patient> {
+        id.parse::<usize>().ok()
+            .filter(|&i| i >= 1 && i <= self.patient_table.len())
+            .map(|i| &self.patient_table[i - 1])
+    }
+
+    /// Nurse driving the route at this position of a chromosome
+    #[inline]
+    pub fn nurse(&self, index: usize) -> &Nurse {
+        &self.nurse_table[index]
+    }
 
-pub fn parse_json(filepath: &str) -> TrainData {
-    let data = fs::read_to_string(filepath).expect("Unable to read file");
-    let parsed: TrainData = serde_json::from_str(&data).expect("Unable to parse file");
-    parsed
+    /// True if the nurse at index `nurse` has the skills patient `gene` needs
+    #[inline]
+    pub fn qualified(&self, nurse: usize, gene: u16) -> bool {
+        self.qualifications.allows(nurse, gene)
+    }
+
+    pub fn qualifications(&self) -> &Qualifications {
+        &self.qualifications
+    }
+
+    /// Days in the planning horizon, 1 for single-day instances
+    pub fn planning_days(&self) -> u32 {
+        self.nbr_days.max(1)
+    }
+
+    /// Allowed day patterns of patient `gene`, bit d set if the patient is visited on day d
+    pub fn visit_patterns(&self, gene: u16) -> &[u64] {
+        &self.pattern_table[gene as usize - 1]
+    }
+
+    /// True if some patient has a drop cost, chromosomes then end with a pool of unassigned patients
+    pub fn has_optional_patients(&self) -> bool {
+        self.optional_patients
+    }
+
+    /// Route index of the unassigned pool, after the nurse routes
+    pub fn pool_route(&self) -> Option<usize> {
+        if self.has_optional_patients() { Some(self.nbr_nurses as usize) } else { None }
+    }
+
+    /// True if the timing of a visit can depend on other routes
+    pub fn has_dependencies(&self) -> bool {
+        !self.synchronized.is_empty() || !self.precedences.is_empty()
+    }
+
+    pub fn continuity(&self) -> Option<&Continuity> {
+        self.continuity.as_ref()
+    }
+
+    /// Uses `routes` as the reference for continuity of care, route i driven by nurse i as in
+    /// [`helper::split_into_nurses`](crate::helper::split_into_nurses). Patients unknown to this instance are ignored
+    pub fn set_previous_routes(&mut self, routes: &[Vec<u16>], change_cost: f64) {
+        let num_patients = self.nbr_patients();
+        let mut previous_nurse: Vec<Option<usize>> = vec![None; num_patients + 1];
+        for (nurse, route) in routes.iter().enumerate() {
+            for &gene in route.iter().filter(|&&gene| gene != 0 && gene as usize <= num_patients) {
+                previous_nurse[gene as usize] = Some(nurse);
+            }
+        }
+        self.continuity = Some(Continuity { previous_nurse, change_cost });
+    }
+
+    /// Rebuilds the dense patient and nurse tables from `patients`, `nurses` and `nbr_nurses`.
+    /// Must be called again whenever they are changed after loading.
+    pub fn index_patients(&mut self) {
+        self.patient_table = (1..=self.nbr_patients())
+            .map(|id| self.patients[&id.to_string()].clone())
+            .collect();
+        self.nurse_table = match &self.nurses {
+            Some(nurses) => nurses.clone(),
+            None => vec![Nurse {
+                capacity: self.capacity_nurse,
+                shift_start: 0.0,
+                shift_end: self.depot.return_time,
+                start_location: 0,
+                end_location: 0,
+                cost_per_minute: 0.0,
+                skills: Vec::new(),
+                break_rule: None,
+                max_working_time: None,
+            }; self.nbr_nurses.max(0) as usize],
+        };
+        for nurse in self.nurse_table.iter_mut() {
+            nurse.break_rule = nurse.break_rule.or(self.break_rule);
+            nurse.max_working_time = nurse.max_working_time.or(self.max_working_time);
+        }
+        self.qualifications = Qualifications::new(&self.nurse_table, &self.patient_table);
+        let nbr_days = self.planning_days();
+        self.pattern_table = self.patient_table.iter().map(|p| p.day_patterns(nbr_days)).collect();
+        self.optional_patients = self.patient_table.iter().any(|p| p.drop_cost.is_some());
+    }
+
+    /// Validates the instance again after its public fields changed and rebuilds what is indexed from them.
+    /// Drops the continuity reference, whose genes may be other patients now
+    pub fn reindex(&mut self) -> Result<(), ParseError> {
+        check_structure(self)?;
+        self.continuity = None;
+        self.index_patients();
+        Ok(())
+    }
+
+    /// Number of 0-delimiters a chromosome needs to split it into one route per nurse, and the unassigned pool if there is one
+    pub fn nbr_delimiters(&self) -> usize {
+        (self.nbr_nurses as usize).saturating_sub(1) + self.has_optional_patients() as usize
+    }
 }
 
-pub fn write_solution_to_file(solution: &String) {
-    fs::write("solution.txt", solution).expect("Error writing to file")
+
+/// Reads an instance file, detecting from its content whether it is
+/// course JSON or a Solomon / Gehring & Homberger text instance
+pub fn parse_instance(filepath: &str) -> Result<TrainData, ParseError> {
+    let data = read_file(filepath)?;
+    if data.trim_start().starts_with('{') {
+        parse_json_str(&data)
+    } else if is_solomon(&data) {
+        parse_solomon(&data)
+    } else {
+        Err(ParseError::UnknownFormat { path: filepath.to_string() })
+    }
 }
 
-pub fn pretty_print_solution_to_file(solution: &String) {
-    fs::write("solution_detail.txt", solution).expect("Error writing to file")
-}
\ No newline at end of file
+#[cfg(test)]
+pub fn parse_json(filepath: &str) -> Result<TrainData, ParseError> {
+    parse_json_str(&read_file(filepath)?)
+}
+
+pub fn parse_json_str(data: &str) -> Result<TrainData, ParseError> {
+    let mut parsed: TrainData = serde_json::from_str(data)?;
+    if let (0, Some(nurses)) = (parsed.nbr_nurses, &parsed.nurses) {
+        parsed.nbr_nurses = nurses.len() as i32;
+    }
+    if parsed.travel_times.is_empty() {
+        check_patient_ids(&parsed)?;
+        parsed.travel_times = parsed.distance_matrix();
+    }
+    check_structure(&parsed)?;
+    parsed.index_patients();
+    Ok(parsed)
+}
+
+fn read_file(filepath: &str) -> Result<String, ParseError> {
+    fs::read_to_string(filepath).map_err(|source| ParseError::Read { path: filepath.to_string(), source })
+}
+
+/// Solomon and Gehring & Homberger files share the same layout:
+/// a name line, a VEHICLE section (number, capacity) and a CUSTOMER table
+fn is_solomon(contents: &str) -> bool {
+    contents.contains("VEHICLE") && contents.contains("CUSTOMER")
+}
+
+/// Parses a Solomon / Gehring & Homberger VRPTW instance.
+///
+/// Customer 0 is the depot, its due date becomes the depot return time.
+/// Solomon due dates are the latest *start* of service, while `end_time` is the latest
+/// time care can be finished, so the service time is added to the due date.
+/// Travel times are the exact euclidean distances between coordinates, no benchmark is known.
+pub fn parse_solomon(contents: &str) -> Result<TrainData, ParseError> {
+    let mut lines = contents.lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty());
+    let instance_name = lines.next().ok_or(ParseError::MissingSection("instance name"))?.1.to_string();
+
+    let mut vehicle: Option<(i32, i32)> = None;
+    let mut rows: Vec<Vec<f64>> = Vec::new();
+    let mut depot_line = 0;
+    while let Some((line_nr, line)) = lines.next() {
+        if line.starts_with("NUMBER") {
+            let (line_nr, line) = lines.next().ok_or(ParseError::MissingSection("VEHICLE"))?;
+            let values: Vec<i32> = line.split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| ParseError::InvalidRow { line: line_nr, content: line.to_string() })?;
+            if values.len() != 2 {
+                return Err(ParseError::InvalidRow { line: line_nr, content: line.to_string() });
+            }
+            vehicle = Some((values[0], values[1]));
+        } else if line.starts_with(|c: char| c.is_ascii_digit()) {
+            let row: Vec<f64> = line.split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| ParseError::InvalidRow { line: line_nr, content: line.to_string() })?;
+            if row.len() != 7 {
+                return Err(ParseError::InvalidRow { line: line_nr, content: line.to_string() });
+            }
+            if rows.is_empty() {
+                depot_line = line_nr;
+            }
+            rows.push(row);
+        }
+    }
+    let (nbr_nurses, capacity_nurse) = vehicle.ok_or(ParseError::MissingSection("VEHICLE"))?;
+    if rows.is_empty() {
+        return Err(ParseError::MissingSection("CUSTOMER"));
+    }
+
+    // The first customer row is the depot, anything else means the rows are shifted or reordered
+    let depot_row = &rows[0];
+    if depot_row[0] != 0.0 || depot_row[3] != 0.0 || depot_row[6] != 0.0 {
+        return Err(ParseError::InvalidField { field: "depot", reason: format!(
+            "line {} is customer {} with demand {} and service time {}, expected customer 0 with neither",
+            depot_line, depot_row[0], depot_row[3], depot_row[6]) });
+    }
+    let depot = Depot {
+        return_time: depot_row[5],
+        x_coord: depot_row[1],
+        y_coord: depot_row[2],
+    };
+
+    let patients: HashMap<String, Patient> = rows.iter().skip(1)
+        .map(|row| (
+            (row[0] as usize).to_string(),
+            Patient {
+                care_time: row[6],
+                demand: row[3] as i32,
+                end_time: row[5] + row[6],
+                start_time: row[4],
+                x_coord: row[1],
+                y_coord: row[2],
+                time_window: TimeWindowKind::Hard,
+                earliness_cost: 0.0,
+                tardiness_cost: 0.0,
+                skills: Vec::new(),
+                frequency: None,
+                patterns: Vec::new(),
+                drop_cost: None,
+            }))
+        .collect();
+
+    let mut parsed = TrainData {
+        instance_name,
+        nbr_nurses,
+        capacity_nurse,
+        benchmark: 0.0,
+        depot,
+        patients,
+        travel_times: Vec::new(),
+        distance_metric: DistanceMetric::Euclidean,
+        nurses: None,
+        nbr_days: 0,
+        synchronized: Vec::new(),
+        precedences: Vec::new(),
+        break_rule: None,
+        max_working_time: None,
+        speed_profile: Vec::new(),
+        uncertainty: None,
+        patient_table: Vec::new(),
+        nurse_table: Vec::new(),
+        qualifications: Qualifications::default(),
+        continuity: None,
+        pattern_table: Vec::new(),
+        optional_patients: false,
+    };
+    check_patient_ids(&parsed)?;
+    parsed.travel_times = parsed.distance_matrix();
+    check_structure(&parsed)?;
+    parsed.index_patients();
+    Ok(parsed)
+}
+
+/// Patient ids must be exactly 1..=n
+fn check_patient_ids(data: &TrainData) -> Result<(), ParseError> {
+    let num_patients = data.nbr_patients();
+    let mut seen = vec![false; num_patients + 1];
+    for id in data.patients.keys() {
+        // Ids above n are fine here, with n keys they leave a gap found below
+        match id.parse::<usize>() {
+            Ok(n) if n >= 1 => if n <= num_patients { seen[n] = true },
+            _ => return Err(ParseError::InvalidPatientId { id: id.clone() }),
+        }
+    }
+    if let Some(missing) = (1..=num_patients).find(|&i| !seen[i]) {
+        return Err(ParseError::MissingPatient { id: missing });
+    }
+    Ok(())
+}
+
+/// Checks what the solver relies on without further checks:
+/// patient ids are exactly "1".."n", `travel_times` is (n+1)x(n+1)
+/// and no patient has negative care time or demand
+fn check_structure(data: &TrainData) -> Result<(), ParseError> {
+    if data.nbr_nurses < 1 {
+        return Err(ParseError::InvalidField { field: "nbr_nurses", reason: format!("{} nurses, need at least one", data.nbr_nurses) });
+    }
+    check_patient_ids(data)?;
+
+    let num_patients = data.nbr_patients();
+    // Extra nodes after the patients are only there for nurses to start or end at
+    let size = if data.nurses.is_some() { data.travel_times.len().max(num_patients + 1) } else { num_patients + 1 };
+    if data.travel_times.len() != size {
+        return Err(ParseError::MatrixRows { expected: size, found: data.travel_times.len() });
+    }
+    if let Some((row, r)) = data.travel_times.iter().enumerate().find(|(_, r)| r.len() != size) {
+        return Err(ParseError::NonSquareMatrix { row, expected: size, found: r.len() });
+    }
+
+    if let Some(nurses) = &data.nurses {
+        if nurses.len() != data.nbr_nurses as usize {
+            return Err(ParseError::InvalidField { field: "nurses", reason: format!("{} nurses listed, nbr_nurses is {}", nurses.len(), data.nbr_nurses) });
+        }
+        for (i, nurse) in nurses.iter().enumerate() {
+            let is_patient = |location: usize| location >= 1 && location <= num_patients;
+            if [nurse.start_location, nurse.end_location].iter().any(|&l| l >= size || is_patient(l)) {
+                return Err(ParseError::InvalidField { field: "nurses", reason: format!("nurse {} starts or ends at a patient or outside the travel matrix", i) });
+            }
+            if nurse.capacity < 0 || nurse.cost_per_minute < 0.0 || nurse.shift_end < nurse.shift_start {
+                return Err(ParseError::InvalidField { field: "nurses", reason: format!("nurse {} has a negative capacity, cost or shift", i) });
+            }
+        }
+    }
+
+    let mut rules = data.break_rule.iter().chain(data.nurses.iter().flatten().filter_map(|n| n.break_rule.as_ref()));
+    if let Some(rule) = rules.find(|r| r.duration < 0.0 || r.start + r.duration > r.end) {
+        return Err(ParseError::InvalidField { field: "break_rule", reason: format!("{} minutes do not fit between {} and {}", rule.duration, rule.start, rule.end) });
+    }
+    let mut limits = data.max_working_time.iter().chain(data.nurses.iter().flatten().filter_map(|n| n.max_working_time.as_ref()));
+    if let Some(limit) = limits.find(|&&limit| limit <= 0.0) {
+        return Err(ParseError::InvalidField { field: "max_working_time", reason: format!("{} minutes, must be positive", limit) });
+    }
+    if let Some(slot) = data.speed_profile.iter().find(|s| !(s.speed > 0.0 && s.speed.is_finite())) {
+        return Err(ParseError::InvalidField { field: "speed_profile", reason: format!("speed {} from {}, must be positive", slot.speed, slot.start) });
+    }
+    if let Some(pair) = data.speed_profile.windows(2).find(|pair| pair[1].start <= pair[0].start) {
+        return Err(ParseError::InvalidField { field: "speed_profile", reason: format!("slot starting at {} after the one at {}", pair[1].start, pair[0].start) });
+    }
+    if let Some(uncertainty) = &data.uncertainty {
+        if !uncertainty.travel.is_valid() || !uncertainty.care.is_valid() {
+            return Err(ParseError::InvalidField { field: "uncertainty", reason: format!("{:?} and {:?}, need sigma >= 0 and spread in [0, 1]", uncertainty.travel, uncertainty.care) });
+        }
+        if uncertainty.samples == 0 || uncertainty.fitness_weight < 0.0 {
+            return Err(ParseError::InvalidField { field: "uncertainty", reason: format!("{} samples with fitness weight {}, need a sample and a weight >= 0", uncertainty.samples, uncertainty.fitness_weight) });
+        }
+    }
+
+    let mut skills: Vec<&String> = data.patients.values().flat_map(|p| p.skills.iter())
+        .chain(data.nurses.iter().flatten().flat_map(|n| n.skills.iter()))
+        .collect();
+    skills.sort();
+    skills.dedup();
+    if skills.len() > MAX_SKILLS {
+        return Err(ParseError::InvalidField { field: "skills", reason: format!("{} distinct skills, at most {} are supported", skills.len(), MAX_SKILLS) });
+    }
+
+    if data.nbr_days > MAX_DAYS {
+        return Err(ParseError::InvalidField { field: "nbr_days", reason: format!("{} days, at most {} are supported", data.nbr_days, MAX_DAYS) });
+    }
+    let nbr_days = data.nbr_days.max(1);
+
+    let is_patient = |gene: u16| gene >= 1 && gene as usize <= num_patients;
+    if let Some(pair) = data.synchronized.iter().find(|[a, b]| !is_patient(*a) || !is_patient(*b) || a == b) {
+        return Err(ParseError::InvalidField { field: "synchronized", reason: format!("pair {:?} needs two different patients", pair) });
+    }
+    for p in data.precedences.iter() {
+        if !is_patient(p.first) || !is_patient(p.then) || p.first == p.then {
+            return Err(ParseError::InvalidField { field: "precedences", reason: format!("{} before {} needs two different patients", p.first, p.then) });
+        }
+        if p.min_gap < 0.0 || p.max_gap.is_some_and(|max| max < p.min_gap) {
+            return Err(ParseError::InvalidField { field: "precedences", reason: format!("{} before {} has gaps {} to {:?}", p.first, p.then, p.min_gap, p.max_gap) });
+        }
+    }
+
+    for id in 1..=num_patients {
+        let key = id.to_string();
+        let patient = &data.patients[&key];
+        if patient.care_time < 0.0 {
+            return Err(ParseError::NegativeValue { patient: key, field: "care_time", value: patient.care_time });
+        }
+        if patient.demand < 0 {
+            return Err(ParseError::NegativeValue { patient: key, field: "demand", value: patient.demand as f64 });
+        }
+        if patient.earliness_cost < 0.0 {
+            return Err(ParseError::NegativeValue { patient: key, field: "earliness_cost", value: patient.earliness_cost });
+        }
+        if patient.tardiness_cost < 0.0 {
+            return Err(ParseError::NegativeValue { patient: key, field: "tardiness_cost", value: patient.tardiness_cost });
+        }
+        if let Some(drop_cost) = patient.drop_cost.filter(|&cost| cost < 0.0) {
+            return Err(ParseError::NegativeValue { patient: key, field: "drop_cost", value: drop_cost });
+        }
+        if let Some(frequency) = patient.frequency.filter(|&f| f < 1 || f > nbr_days) {
+            return Err(ParseError::InvalidField { field: "frequency", reason: format!("patient {} needs {} visits in {} days", key, frequency, nbr_days) });
+        }
+        for days in patient.patterns.iter() {
+            let mut sorted = days.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            let frequency = patient.frequency.unwrap_or(patient.patterns[0].len() as u32);
+            if days.is_empty() || sorted.len() != days.len() || days.len() as u32 != frequency || sorted.iter().any(|&d| d >= nbr_days) {
+                return Err(ParseError::InvalidField { field: "patterns", reason: format!("patient {} has pattern {:?}, need {} different days below {}", key, days, frequency, nbr_days) });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads a `solution.json` with its schedule
+pub fn parse_solution_json(filepath: &str) -> Result<Solution, ParseError> {
+    Ok(serde_json::from_str(&read_file(filepath)?)?)
+}
+
+/// Reads every solution in a file, either `solution.json` or the bracket format of `solution.txt`
+pub fn parse_solution_file(filepath: &str) -> Result<Vec<RoutePlan>, ParseError> {
+    parse_solution_str(&read_file(filepath)?)
+}
+
+/// A `solution.json` with either a Pareto front or a single solution
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SolutionFile {
+    Front(ParetoFront),
+    Single(Solution),
+}
+
+/// The bracket format is an instance name line followed by one `[[1,2],[],[3]]` line per solution,
+/// each optionally followed by a `<fitness> <feasible>` line
+pub fn parse_solution_str(contents: &str) -> Result<Vec<RoutePlan>, ParseError> {
+    if contents.trim_start().starts_with('{') {
+        return match serde_json::from_str(contents)? {
+            SolutionFile::Front(front) => Ok(front.solutions.iter().map(Solution::route_plan).collect()),
+            SolutionFile::Single(solution) => Ok(vec![solution.route_plan()]),
+        };
+    }
+
+    let mut instance_name: Option<String> = None;
+    let mut plans: Vec<RoutePlan> = Vec::new();
+    for (i, line) in contents.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() {
+            continue;
+        }
+        let invalid = || ParseError::InvalidRow { line: i, content: line.to_string() };
+        // Unassigned patients are the ones the routes leave out
+        if line.starts_with("Unassigned") {
+            continue;
+        }
+        if line.starts_with('[') {
+            let routes: Vec<Vec<u16>> = serde_json::from_str(line).map_err(|_| invalid())?;
+            plans.push(RoutePlan { instance_name: instance_name.clone(), routes, reported_fitness: None });
+        } else if let Some(plan) = plans.last_mut().filter(|p| p.reported_fitness.is_none()) {
+            let fitness = line.split_whitespace().next().and_then(|f| f.parse().ok()).ok_or_else(invalid)?;
+            plan.reported_fitness = Some(fitness);
+        } else if instance_name.is_none() && plans.is_empty() {
+            instance_name = Some(line.to_string());
+        } else {
+            return Err(invalid());
+        }
+    }
+    if plans.is_empty() {
+        return Err(ParseError::MissingSection("route list"));
+    }
+    Ok(plans)
+}
+
+pub fn write_solution_to_file(solution: &str) -> Result<(), IoError> {
+    write_file("solution.txt", solution)
+}
+
+pub fn pretty_print_solution_to_file(solution: &str) -> Result<(), IoError> {
+    write_file("solution_detail.txt", solution)
+}
+
+pub fn write_solution_json(solution: &Solution) -> Result<(), IoError> {
+    let json = serde_json::to_string_pretty(solution).map_err(IoError::Serialize)?;
+    write_file("solution.json", &json)
+}
+
+pub fn write_pareto_json(front: &ParetoFront) -> Result<(), IoError> {
+    let json = serde_json::to_string_pretty(front).map_err(IoError::Serialize)?;
+    write_file("pareto_front.json", &json)
+}
+
+pub fn write_week_json(week: &WeekSolution) -> Result<(), IoError> {
+    let json = serde_json::to_string_pretty(week).map_err(IoError::Serialize)?;
+    write_file("week_solution.json", &json)
+}
+
+fn write_file(path: &str, contents: &str) -> Result<(), IoError> {
+    fs::write(path, contents).map_err(|source| IoError::Write { path: path.to_string(), source })
+}
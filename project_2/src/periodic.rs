@@ -0,0 +1,187 @@
+//! Multi-day planning. Every patient gets one of its allowed day patterns, and each day is routed
+//! like a single-day instance holding only the patients visited that day.
+
+use rand::{Rng, RngCore};
+use rand::seq::SliceRandom;
+use crate::evaluator::{self, PenaltyConfig, RouteEvaluator, RouteStats};
+use crate::helper;
+use crate::individual::chromosome::Chromosome;
+use crate::mutation::{MutationHolder, Touched};
+use crate::parsing::TrainData;
+use crate::population_init::individual_init::assign_qualified;
+use crate::solution::{Solution, WeekSolution};
+
+/// Chance an improvement step changes a patient's day pattern instead of a day's routes
+const PATTERN_CHANCE: f64 = 0.1;
+
+/// Day pattern of every patient together with the routes of every day
+#[derive(Debug, Clone)]
+pub struct WeekPlan {
+    /// Index into `TrainData::visit_patterns` per patient, patient i at index i - 1
+    pub patterns: Vec<usize>,
+    /// 0-delimited chromosome of each day, with the patients visited that day
+    pub days: Vec<Chromosome>,
+}
+
+impl WeekPlan {
+    /// Random pattern per patient and random routes every day
+    pub fn random(rng: &mut dyn RngCore, t_data: &TrainData) -> Self {
+        let patterns: Vec<usize> = (1..=t_data.nbr_patients() as u16)
+            .map(|gene| rng.gen_range(0..t_data.visit_patterns(gene).len()))
+            .collect();
+        let days = (0..t_data.planning_days())
+            .map(|day| {
+                let mut genes: Vec<u16> = (1..=t_data.nbr_patients() as u16)
+                    .filter(|&gene| t_data.visit_patterns(gene)[patterns[gene as usize - 1]] & 1 << day != 0)
+                    .collect();
+                genes.extend(std::iter::repeat_n(0, t_data.nbr_delimiters()));
+                genes.shuffle(rng);
+                assign_qualified(rng, genes.into_iter().collect(), t_data)
+            })
+            .collect();
+        Self { patterns, days }
+    }
+
+    /// Days patient `gene` should be visited, bit d set for day d
+    pub fn pattern(&self, gene: u16, t_data: &TrainData) -> u64 {
+        t_data.visit_patterns(gene)[self.patterns[gene as usize - 1]]
+    }
+
+    /// Patients visited on other days than their pattern says, or more than once on a day
+    pub fn pattern_violations(&self, t_data: &TrainData) -> u32 {
+        let mut visits = vec![0u64; t_data.nbr_patients() + 1];
+        let mut duplicates = vec![false; t_data.nbr_patients() + 1];
+        for (day, chromo) in self.days.iter().enumerate() {
+            for &gene in chromo.iter().filter(|&&gene| gene != 0) {
+                duplicates[gene as usize] |= visits[gene as usize] & 1 << day != 0;
+                visits[gene as usize] |= 1 << day;
+            }
+        }
+        (1..=t_data.nbr_patients() as u16)
+            .filter(|&gene| duplicates[gene as usize] || visits[gene as usize] != self.pattern(gene, t_data))
+            .count() as u32
+    }
+
+    /// Route statistics of every day, evaluated as single-day instances
+    pub fn evaluate(&self, t_data: &TrainData) -> Vec<Vec<RouteStats>> {
+        let evaluator = RouteEvaluator::new(t_data);
+        self.days.iter().map(|chromo| evaluator.evaluate_chromosome(chromo)).collect()
+    }
+
+    /// Sum of the daily (fitness, broken constraints), every pattern violation breaks one more constraint
+    pub fn fitness(&self, stats: &[Vec<RouteStats>], t_data: &TrainData, penalty: &PenaltyConfig) -> (f64, f64) {
+        let (fitness, breaks) = stats.iter()
+            .map(|day| evaluator::fitness(day, penalty))
+            .fold((0.0, 0.0), |total, day| (total.0 + day.0, total.1 + day.1));
+        (fitness, breaks + self.pattern_violations(t_data) as f64)
+    }
+
+    /// Gives a random patient with a choice of patterns another one, and moves its visits to random
+    /// positions of the new days, in the route of a qualified nurse. Without one an optional patient goes to the
+    /// unassigned pool, others stay with the nurse they had on another day. Returns the days that changed
+    pub fn change_pattern(&mut self, rng: &mut dyn RngCore, t_data: &TrainData) -> Vec<usize> {
+        let choices: Vec<u16> = (1..=t_data.nbr_patients() as u16).filter(|&gene| t_data.visit_patterns(gene).len() > 1).collect();
+        let gene = match choices.choose(rng) {
+            Some(&gene) => gene,
+            None => return Vec::new(),
+        };
+        let old = self.pattern(gene, t_data);
+        let mut new_index = rng.gen_range(0..t_data.visit_patterns(gene).len() - 1);
+        if new_index >= self.patterns[gene as usize - 1] {
+            new_index += 1;
+        }
+        self.patterns[gene as usize - 1] = new_index;
+        let new = self.pattern(gene, t_data);
+        let nurses = t_data.nbr_nurses as usize;
+        let previous = self.days.iter()
+            .find_map(|chromo| helper::route_ranges(chromo).into_iter().take(nurses).position(|r| chromo.genes[r].contains(&gene)));
+
+        let mut changed: Vec<usize> = Vec::new();
+        for (day, chromo) in self.days.iter_mut().enumerate() {
+            let bit = 1u64 << day;
+            if old & bit != 0 && new & bit == 0 {
+                chromo.genes.retain(|&g| g != gene);
+            } else if old & bit == 0 && new & bit != 0 {
+                let qualified: Vec<usize> = t_data.qualifications().qualified_nurses(gene).collect();
+                let routes = helper::route_ranges(chromo);
+                let nurse = match (qualified.choose(rng), t_data.pool_route()) {
+                    (Some(&nurse), _) => nurse,
+                    (None, Some(pool)) if t_data.patient(gene).drop_cost.is_some() => pool,
+                    (None, _) => previous.unwrap_or_else(|| rng.gen_range(0..nurses)),
+                };
+                let position = rng.gen_range(routes[nurse].start..=routes[nurse].end);
+                chromo.genes.insert(position, gene);
+            } else {
+                continue;
+            }
+            changed.push(day);
+        }
+        changed
+    }
+
+    /// Machine-readable plan with the schedule of every day
+    pub fn week_solution(&self, t_data: &TrainData) -> WeekSolution {
+        let days: Vec<Solution> = self.days.iter().map(|chromo| Solution::from_chromosome(chromo, t_data)).collect();
+        let patient_days = (1..=t_data.nbr_patients() as u16)
+            .map(|gene| {
+                let pattern = self.pattern(gene, t_data);
+                (0..t_data.planning_days()).filter(|&day| pattern & 1 << day != 0).collect()
+            })
+            .collect();
+        WeekSolution {
+            instance_name: t_data.instance_name.clone(),
+            objective: days.iter().map(|d| d.objective).sum(),
+            feasible: self.pattern_violations(t_data) == 0 && days.iter().all(|d| d.feasible),
+            seed: None,
+            run_time_secs: 0.0,
+            patient_days,
+            days,
+        }
+    }
+}
+
+/// Hill climbing on a week plan: every step changes a pattern or mutates the routes of one day,
+/// and is kept unless the week gets worse. Only the changed days are evaluated again.
+/// Not a [`GenAlg`](crate::gen_alg::GenAlg) per day: a pattern change moves visits between days, so the days
+/// can not evolve as separate populations. The steps use the islands' mutation operators
+pub fn improve(rng: &mut dyn RngCore, mut plan: WeekPlan, t_data: &TrainData, mutations: &MutationHolder, penalty: &PenaltyConfig, iterations: usize) -> WeekPlan {
+    let evaluator = RouteEvaluator::new(t_data);
+    let mut stats = plan.evaluate(t_data);
+    let mut fitness = plan.fitness(&stats, t_data, penalty);
+
+    for _ in 0..iterations {
+        if rng.gen_bool(PATTERN_CHANCE) {
+            let mut candidate = plan.clone();
+            let changed = candidate.change_pattern(rng, t_data);
+            if changed.is_empty() {
+                continue;
+            }
+            let mut candidate_stats = stats.clone();
+            for &day in changed.iter() {
+                candidate_stats[day] = evaluator.evaluate_chromosome(&candidate.days[day]);
+            }
+            let candidate_fitness = candidate.fitness(&candidate_stats, t_data, penalty);
+            if candidate_fitness <= fitness {
+                plan = candidate;
+                stats = candidate_stats;
+                fitness = candidate_fitness;
+            }
+        } else {
+            let day = rng.gen_range(0..plan.days.len());
+            let mut chromo = plan.days[day].clone();
+            let touched = mutations.mutations().iter().fold(Touched::Unchanged, |touched, m| touched.merge(m.mutate(rng, &mut chromo)));
+            if touched == Touched::Unchanged {
+                continue;
+            }
+            let mut candidate_stats = stats.clone();
+            candidate_stats[day] = evaluator.reevaluate_chromosome(&chromo, &stats[day], &touched);
+            let candidate_fitness = plan.fitness(&candidate_stats, t_data, penalty);
+            if candidate_fitness <= fitness {
+                plan.days[day] = chromo;
+                stats = candidate_stats;
+                fitness = candidate_fitness;
+            }
+        }
+    }
+    plan
+}
@@ -1,7 +1,7 @@
 use crate::parsing::TrainData;
 use plotters::prelude::*;
 pub fn plot_train_file(data: &TrainData) {
-    let m: Vec<(i32, i32)> = data.patients.iter().map(|(a, b)| (b.x_coord, b.y_coord)).collect();
+    let m: Vec<(i32, i32)> = data.patients.values().map(|b| (b.x_coord as i32, b.y_coord as i32)).collect();
     let root_area = BitMapBackend::new("test.png", (600, 400)).into_drawing_area();
     root_area.fill(&WHITE).unwrap();
 
@@ -21,7 +21,7 @@ pub fn plot_train_file(data: &TrainData) {
 
     // Draw Scatter Plot
     ctx.draw_series(
-        m.iter().map(|point| Circle::new(*point, 4i32, &BLUE)),
+        m.iter().map(|point| Circle::new(*point, 4i32, BLUE)),
     ).unwrap();
     println!("{:?}", max_x);
     println!("{:?}", max_y);
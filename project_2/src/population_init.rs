@@ -3,8 +3,11 @@ use crate::individual::individual::Route;
 use crate::parsing::TrainData;
 use crate::individual::chromosome::Chromosome;
 use rand::prelude::SliceRandom;
+use rand::Rng;
+use crate::helper;
 use crate::population_init::individual_init::random_chromo;
 use crate::individual::individual::{calculate_fitness, Individual};
+use crate::fitness::FitnessFunction;
 //use kmeans::*;
 
 pub(crate) mod individual_init {
@@ -14,50 +17,30 @@ pub(crate) mod individual_init {
 
         //TODO: thread_rng? or keep using seeded chacha8?
         //let mut rng = ChaCha8Rng::from_seed([42; 32]);
-        let mut vec: Vec<u16> = (1..101).collect();
-        //add nurse delimiters
-        vec.append(&mut vec![0u16; 24]);
-        vec.shuffle(rng);
-        let chromo: Chromosome = vec.into_iter().collect();
+        let chromo = random_chromo(rng, t_data);
         let (fitness, feasible)= calculate_fitness(&chromo, t_data);
-        let r = Route::create(chromo, fitness, !(feasible > 0.0));
-        r
+        Route::create(chromo, fitness, feasible <= 0.0)
 
     }
 
     pub fn nurse_distributed_route(rng: &mut dyn RngCore, t_data: &TrainData) -> Route {
-        //TODO: thread_rng? or keep using seeded chacha8?
-        //let mut rng = ChaCha8Rng::from_seed([42; 32]);
-
-        let num_nurses: usize = t_data.nbr_nurses as usize;
-        let num_patients: usize = t_data.patients.len();
-        let step = (num_patients as f32 / num_nurses as f32).round() as usize;
-        let mut vec: Vec<u16> = (1..(num_patients+1) as u16).collect();
-        //add nurse delimiters
-        //vec.append(&mut vec![0u16; 24]);
-        vec.shuffle(rng);
-        for i in 1..num_nurses {
-            let index = (i * (step + 1)) - 1;
-            if index > vec.len() {
-                vec.insert(vec.len(), 0);
-            } else { vec.insert(index, 0) }
-        }
-        let chromo: Chromosome = vec.into_iter().collect();
+        let chromo = nurse_distributed_chromo(rng, t_data);
         let (fitness, feasible)= calculate_fitness(&chromo, t_data);
-        let r = Route::create(chromo, fitness, !(feasible > 0.0));
-        r
+        Route::create(chromo, fitness, feasible <= 0.0)
     }
 
-    pub fn random_chromo(rng: &mut dyn RngCore) -> Chromosome {
-        let mut vec: Vec<u16> = (1..101).collect();
+    pub fn random_chromo(rng: &mut dyn RngCore, t_data: &TrainData) -> Chromosome {
+        let num_patients = t_data.nbr_patients() as u16;
+        let mut vec: Vec<u16> = (1..num_patients + 1).collect();
         //add nurse delimiters
-        vec.append(&mut vec![0u16; 24]);
+        vec.append(&mut vec![0u16; t_data.nbr_delimiters()]);
         vec.shuffle(rng);
         let chromo: Chromosome = vec.into_iter().collect();
-        chromo
+        assign_qualified(rng, chromo, t_data)
     }
 
-    pub fn random_chromo_no_delimit(rng: &mut dyn RngCore, num_patients: u16) -> Chromosome {
+    pub fn random_chromo_no_delimit(rng: &mut dyn RngCore, t_data: &TrainData) -> Chromosome {
+        let num_patients = t_data.nbr_patients() as u16;
         let mut vec: Vec<u16> = (1..num_patients + 1).collect();
         vec.shuffle(rng);
         let chromo: Chromosome = vec.into_iter().collect();
@@ -70,7 +53,28 @@ pub(crate) mod individual_init {
         //let mut rng = ChaCha8Rng::from_seed([42; 32]);
 
         let num_nurses: usize = t_data.nbr_nurses as usize;
-        let num_patients: usize = t_data.patients.len();
+        let num_patients: usize = t_data.nbr_patients();
+        if t_data.nurses.is_some() {
+            // Heterogeneous fleet: split the patients in proportion to each nurse's capacity
+            let mut patients: Vec<u16> = (1..(num_patients + 1) as u16).collect();
+            patients.shuffle(rng);
+            let total_capacity: i32 = (0..num_nurses).map(|n| t_data.nurse(n).capacity).sum::<i32>().max(1);
+            let mut vec: Vec<u16> = Vec::with_capacity(num_patients + num_nurses);
+            let mut capacity: i32 = 0;
+            let mut taken: usize = 0;
+            for nurse in 0..num_nurses {
+                capacity += t_data.nurse(nurse).capacity;
+                let until = if nurse == num_nurses - 1 { num_patients } else { (num_patients * capacity as usize / total_capacity as usize).max(taken) };
+                vec.extend_from_slice(&patients[taken..until]);
+                taken = until;
+                if nurse < num_nurses - 1 {
+                    vec.push(0);
+                }
+            }
+            // Every patient starts out assigned, the unassigned pool is empty
+            vec.extend(t_data.pool_route().map(|_| 0u16));
+            return assign_qualified(rng, vec.into_iter().collect(), t_data);
+        }
         let step = (num_patients as f32 / num_nurses as f32).round() as usize;
 
         let mut vec: Vec<u16> = (1..(num_patients+1) as u16).collect();
@@ -83,8 +87,30 @@ pub(crate) mod individual_init {
                 vec.insert(vec.len(), 0);
             } else { vec.insert(index, 0) }
         }
+        vec.extend(t_data.pool_route().map(|_| 0u16));
         let chromo: Chromosome = vec.into_iter().collect();
-        chromo
+        assign_qualified(rng, chromo, t_data)
+    }
+
+    /// Moves every patient on the route of a nurse without the skills it needs to a random qualified nurse.
+    /// Patients no nurse is qualified for stay where they are
+    pub fn assign_qualified(rng: &mut dyn RngCore, chromo: Chromosome, t_data: &TrainData) -> Chromosome {
+        if !t_data.qualifications().is_restricted() {
+            return chromo;
+        }
+        let mut routes: Vec<Vec<u16>> = helper::route_ranges(&chromo).into_iter().map(|r| chromo.genes[r].to_vec()).collect();
+        let mut misplaced: Vec<(usize, u16)> = Vec::new();
+        for (nurse, route) in routes.iter_mut().enumerate() {
+            misplaced.extend(route.iter().filter(|&&gene| !t_data.qualified(nurse, gene)).map(|&gene| (nurse, gene)));
+            route.retain(|&gene| t_data.qualified(nurse, gene));
+        }
+        for (nurse, gene) in misplaced {
+            let qualified: Vec<usize> = t_data.qualifications().qualified_nurses(gene).collect();
+            let to = *qualified.choose(rng).unwrap_or(&nurse);
+            let position = rng.gen_range(0..=routes[to].len());
+            routes[to].insert(position, gene);
+        }
+        helper::combine_into_chromo(&routes)
     }
 }
 
@@ -96,8 +122,8 @@ pub(crate) mod pop_init {
 
     pub trait PopulationGenerator
     {
-        fn generate_population<I>(&self, data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
-        where I: Individual;
+        fn generate_population<I, F>(&self, data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I>
+        where I: Individual, F: FitnessFunction;
     }
 
     pub struct RandomPopulation;
@@ -108,14 +134,14 @@ pub(crate) mod pop_init {
 
     impl PopulationGenerator for RandomPopulation
     {
-        fn generate_population<I>(&self, data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
-        where I: Individual
+        fn generate_population<I, F>(&self, data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I>
+        where I: Individual, F: FitnessFunction
         {
             let mut population: Vec<I> = Vec::new();
             for _ in 0..pop_size {
-                let chromo = random_chromo(rng);
-                let (fitness, feasible) = calculate_fitness(&chromo, &data);
-                population.push(I::create(chromo, fitness, !(feasible > 0.0)));
+                let chromo = random_chromo(rng, data);
+                let (fitness, feasible) = fitness_function.calculate_fitness(&chromo);
+                population.push(I::create(chromo, fitness, feasible <= 0.0));
             }
             population
         }
@@ -130,14 +156,14 @@ pub(crate) mod pop_init {
 
     impl PopulationGenerator for RandomPopulationNoDelim
     {
-        fn generate_population<I>(&self, data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
-            where I: Individual
+        fn generate_population<I, F>(&self, data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I>
+            where I: Individual, F: FitnessFunction
         {
             let mut population: Vec<I> = Vec::new();
             for _ in 0..pop_size {
-                let chromo = random_chromo_no_delimit(rng, data.patients.len() as u16);
-                let (fitness, feasible) = calculate_fitness(&chromo, &data);
-                population.push(I::create(chromo, fitness, !(feasible > 0.0)));
+                let chromo = random_chromo_no_delimit(rng, data);
+                let (fitness, feasible) = fitness_function.calculate_fitness(&chromo);
+                population.push(I::create(chromo, fitness, feasible <= 0.0));
             }
             population
         }
@@ -150,49 +176,49 @@ pub(crate) mod pop_init {
         pub fn new() -> Self {Self }
     }
     impl PopulationGenerator for BalancedRoutes {
-        fn generate_population<I>(&self, data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I> where I: Individual {
+        fn generate_population<I, F>(&self, data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I> where I: Individual, F: FitnessFunction {
             let mut population: Vec<I> = Vec::new();
             for _ in 0..pop_size {
-                let chromo = nurse_distributed_chromo(rng, &data);
-                let (fitness, feasible) = calculate_fitness(&chromo, &data);
-                population.push(I::create(chromo, fitness, !(feasible > 0.0)))
+                let chromo = nurse_distributed_chromo(rng, data);
+                let (fitness, feasible) = fitness_function.calculate_fitness(&chromo);
+                population.push(I::create(chromo, fitness, feasible <= 0.0))
             }
             population
         }
     }
 
-    pub fn init_pop_random<I>(data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
-        where I: Individual,
+    pub fn init_pop_random<I, F>(data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I>
+        where I: Individual, F: FitnessFunction,
     {
         let mut population: Vec<I> = Vec::new();
         for _ in 0..pop_size {
-            let chromo = random_chromo(rng);
-            let (fitness, feasible) = calculate_fitness(&chromo, &data);
-            population.push(I::create(chromo, fitness, !(feasible > 0.0)))
+            let chromo = random_chromo(rng, data);
+            let (fitness, feasible) = fitness_function.calculate_fitness(&chromo);
+            population.push(I::create(chromo, fitness, feasible <= 0.0))
         }
         population
     }
 
-    pub fn init_pop_random_no_delim<I>(data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
-        where I: Individual,
+    pub fn init_pop_random_no_delim<I, F>(data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I>
+        where I: Individual, F: FitnessFunction,
     {
         let mut population: Vec<I> = Vec::new();
         for _ in 0..pop_size {
-            let chromo = random_chromo_no_delimit(rng, data.patients.len() as u16);
-            let (fitness, feasible) = calculate_fitness(&chromo, &data);
-            population.push(I::create(chromo, fitness, !(feasible > 0.0)))
+            let chromo = random_chromo_no_delimit(rng, data);
+            let (fitness, feasible) = fitness_function.calculate_fitness(&chromo);
+            population.push(I::create(chromo, fitness, feasible <= 0.0))
         }
         population
     }
 
-    pub fn init_pop_even_chromosomes<I>(data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
-        where I: Individual,
+    pub fn init_pop_even_chromosomes<I, F>(data: &TrainData, pop_size: usize, fitness_function: &F, rng: &mut dyn RngCore) -> Vec<I>
+        where I: Individual, F: FitnessFunction,
     {
         let mut population: Vec<I> = Vec::new();
         for _ in 0..pop_size {
-            let chromo = nurse_distributed_chromo(rng, &data);
-            let (fitness, feasible) = calculate_fitness(&chromo, &data);
-            population.push(I::create(chromo, fitness, !(feasible > 0.0)))
+            let chromo = nurse_distributed_chromo(rng, data);
+            let (fitness, feasible) = fitness_function.calculate_fitness(&chromo);
+            population.push(I::create(chromo, fitness, feasible <= 0.0))
         }
         population
     }
@@ -200,7 +226,7 @@ pub(crate) mod pop_init {
     pub fn init_pop_kmeans<I>(data: &TrainData, pop_size: usize, rng: &mut dyn RngCore) -> Vec<I>
         where I: Individual
     {
-        let mut coords = vec![0; data.patients.len() * 2];
+        let mut coords = vec![0.0; data.patients.len() * 2];
 
         for (_, p) in data.patients.iter() {
             coords.push(p.x_coord);
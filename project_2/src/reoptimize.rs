@@ -0,0 +1,323 @@
+//! Re-planning during the day. Visits done or underway stay as they are, the rest of every route is planned again
+//! together with new patients on a sub-instance in which each nurse starts where and when its fixed visits end,
+//! so the GA and its operators only see the unfixed suffixes.
+
+use std::collections::HashMap;
+use rand_chacha::rand_core::RngCore;
+use crate::config::GaConfig;
+use crate::crossover::OrderOneCrossover;
+use crate::error::ParseError;
+use crate::evaluator::RouteEvaluator;
+use crate::fitness::{FitnessFunction, ObjectiveFitness};
+use crate::gen_alg::GenAlg;
+use crate::helper;
+use crate::individual::chromosome::Chromosome;
+use crate::individual::individual::{Individual, Route};
+use crate::parsing::{Precedence, TrainData};
+use crate::population_init::pop_init;
+use crate::selection::{ElitismSurvivorSelection, TournamentParentSelection};
+use crate::solution::{self, NurseRoute, RestBreak, Solution, Violation};
+
+/// What happened since a plan was made
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disruption {
+    pub clock: f64,
+    /// Leading visits of every nurse's route that are done or underway
+    pub fixed: Vec<usize>,
+    /// Patients of the instance to visit that the plan does not have yet
+    pub added: Vec<u16>,
+    /// Patients of the plan that no longer need a visit
+    pub cancelled: Vec<u16>,
+}
+
+impl Disruption {
+    /// Fixes the visits of `solution` started by `clock`. A nurse on the way to a patient may still be sent elsewhere
+    pub fn at(clock: f64, solution: &Solution) -> Self {
+        Self {
+            clock,
+            fixed: solution.routes.iter().map(|r| r.visits.iter().take_while(|v| v.start <= clock).count()).collect(),
+            added: Vec::new(),
+            cancelled: Vec::new(),
+        }
+    }
+}
+
+/// The part of an instance still to plan. Its patients are the unfixed and added ones, renumbered from 1,
+/// its nurses start at their last fixed visit when done there, with the capacity and working time they have left
+#[derive(Debug, Clone)]
+pub struct SubInstance {
+    pub data: TrainData,
+    /// Patient of the full instance for every gene of `data`, gene - 1 as index
+    patients: Vec<u16>,
+    /// Fixed visits of every nurse as they were planned
+    fixed: Vec<FixedPart>,
+    /// Unfixed rest of every route of the previous plan and its unassigned pool, in genes of `data`
+    rest: Vec<Vec<u16>>,
+}
+
+/// Start of a route that can not change anymore, with its travel, load, break and violations,
+/// and where and when the nurse is free after it
+#[derive(Debug, Clone)]
+struct FixedPart {
+    route: NurseRoute,
+    location: usize,
+    free: f64,
+}
+
+impl SubInstance {
+    pub fn new(t_data: &TrainData, solution: &Solution, disruption: &Disruption) -> Result<Self, ParseError> {
+        let invalid = |field, reason: String| Err(ParseError::InvalidField { field, reason });
+        if t_data.planning_days() > 1 {
+            return invalid("nbr_days", format!("{} planning days, re-planning works on a single day", t_data.planning_days()));
+        }
+        if solution.routes.len() != t_data.nbr_nurses as usize || disruption.fixed.len() != solution.routes.len() {
+            return invalid("fixed", format!("{} routes with {} fixed counts for {} nurses", solution.routes.len(), disruption.fixed.len(), t_data.nbr_nurses));
+        }
+        if let Some(nurse) = (0..solution.routes.len()).find(|&n| disruption.fixed[n] > solution.routes[n].visits.len()) {
+            return invalid("fixed", format!("{} visits of nurse {} fixed, the route has {}", disruption.fixed[nurse], nurse, solution.routes[nurse].visits.len()));
+        }
+
+        let fixed: Vec<FixedPart> = solution.routes.iter().zip(disruption.fixed.iter())
+            .map(|(route, &fixed)| FixedPart::new(route, fixed, disruption.clock, t_data))
+            .collect();
+        let planned: Vec<u16> = solution.routes.iter().flat_map(|r| r.visits.iter().map(|v| v.patient)).chain(solution.unassigned.iter().copied()).collect();
+        let is_fixed = |gene: &u16| fixed.iter().any(|f| f.route.visits.iter().any(|v| v.patient == *gene));
+        if let Some(gene) = disruption.cancelled.iter().find(|g| !planned.contains(g) || is_fixed(g)) {
+            return invalid("cancelled", format!("patient {} is not in the plan or its visit is done or underway", gene));
+        }
+        let is_patient = |gene: &u16| *gene >= 1 && *gene as usize <= t_data.nbr_patients();
+        if let Some(gene) = disruption.added.iter().find(|g| !is_patient(g) || planned.contains(g)) {
+            return invalid("added", format!("patient {} is not in the instance or already in the plan", gene));
+        }
+
+        // Unfixed visits of every route, then the pool, then the added patients become genes 1..=m
+        let mut rest: Vec<Vec<u16>> = solution.routes.iter().zip(disruption.fixed.iter())
+            .map(|(route, &fixed)| route.visits[fixed..].iter().map(|v| v.patient).collect())
+            .chain(std::iter::once(solution.unassigned.clone()))
+            .collect();
+        for part in rest.iter_mut() {
+            part.retain(|gene| !disruption.cancelled.contains(gene));
+        }
+        let patients: Vec<u16> = rest.iter().flatten().chain(disruption.added.iter()).copied().collect();
+        let gene_of: HashMap<u16, u16> = patients.iter().enumerate().map(|(i, &p)| (p, i as u16 + 1)).collect();
+        for part in rest.iter_mut() {
+            part.iter_mut().for_each(|gene| *gene = gene_of[gene]);
+        }
+
+        // Nodes of the sub-instance: the depot, the patients, then where nurses now are and end
+        let mut nodes: Vec<usize> = std::iter::once(0).chain(patients.iter().map(|&p| p as usize)).collect();
+        let node_of = |node: usize, nodes: &mut Vec<usize>| match nodes.iter().position(|&n| n == node) {
+            Some(index) => index,
+            None => {
+                nodes.push(node);
+                nodes.len() - 1
+            }
+        };
+        let mut nurses = Vec::new();
+        for (index, part) in fixed.iter().enumerate() {
+            let shift = t_data.nurse(index);
+            let mut nurse = shift.clone();
+            nurse.start_location = node_of(part.location, &mut nodes);
+            nurse.end_location = node_of(shift.end_location, &mut nodes);
+            // A nurse past the end of the shift is already late, it stays so from the shift end
+            nurse.shift_start = part.free.min(shift.shift_end);
+            nurse.capacity = (shift.capacity - part.route.load).max(0);
+            nurse.max_working_time = shift.max_working_time
+                .map(|limit| (limit - (part.free - part.route.rest_break.map_or(shift.shift_start, |b| b.end))).max(f64::MIN_POSITIVE));
+            if part.route.rest_break.is_some() {
+                nurse.break_rule = None;
+            }
+            nurses.push(nurse);
+        }
+
+        let mut data = t_data.clone();
+        data.travel_times = nodes.iter().map(|&from| nodes.iter().map(|&to| t_data.travel_times[from][to]).collect()).collect();
+        data.patients = patients.iter().enumerate()
+            .map(|(i, &p)| ((i + 1).to_string(), t_data.patient(p).clone()))
+            .collect();
+        data.nurses = Some(nurses);
+        data.break_rule = None;
+        data.max_working_time = None;
+        // Pairs with a fixed visit can not move anymore
+        data.synchronized = t_data.synchronized.iter()
+            .filter_map(|pair| Some([*gene_of.get(&pair[0])?, *gene_of.get(&pair[1])?]))
+            .collect();
+        data.precedences = t_data.precedences.iter()
+            .filter_map(|p| Some(Precedence { first: *gene_of.get(&p.first)?, then: *gene_of.get(&p.then)?, ..*p }))
+            .collect();
+        data.reindex()?;
+
+        Ok(Self { data, patients, fixed, rest })
+    }
+
+    /// The previous plan without its fixed and cancelled visits, with every added patient where it adds least travel
+    /// without breaking a constraint, or at the end of the shortest route
+    pub fn seed_chromosome(&self) -> Chromosome {
+        let nbr_nurses = self.fixed.len();
+        let mut routes: Vec<Vec<u16>> = self.rest[..nbr_nurses].to_vec();
+        let evaluator = RouteEvaluator::new(&self.data);
+        let planned: usize = self.rest.iter().map(Vec::len).sum();
+        for gene in planned as u16 + 1..=self.patients.len() as u16 {
+            let cheapest = routes.iter().enumerate()
+                .flat_map(|(nurse, route)| {
+                    let slack = evaluator.route_slack(nurse, route);
+                    (0..=route.len()).filter_map(move |position| slack.insertion(gene, position, &self.data).map(|cost| (cost, nurse, position)))
+                        .collect::<Vec<_>>()
+                })
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            match cheapest {
+                Some((_, nurse, position)) => routes[nurse].insert(position, gene),
+                None => routes.iter_mut().min_by_key(|r| r.len()).unwrap().push(gene),
+            }
+        }
+        if self.data.has_optional_patients() {
+            routes.push(self.rest[nbr_nurses].clone());
+        } else {
+            // Without optional patients left the pool has to be visited
+            routes[0].extend(self.rest[nbr_nurses].iter());
+        }
+        Chromosome { genes: routes.join(&0) }
+    }
+
+    /// Schedule of the full instance: the fixed visits as planned, followed by the schedule of a chromosome of the sub-instance.
+    /// Fitness and objectives are left out, they need route statistics of the whole day
+    pub fn merge(&self, chromosome: &Chromosome, t_data: &TrainData) -> Solution {
+        let rest = Solution::from_chromosome(chromosome, &self.data);
+        let routes: Vec<NurseRoute> = self.fixed.iter().zip(rest.routes)
+            .map(|(part, route)| self.merge_route(part, route, t_data))
+            .collect();
+        let objective: f64 = routes.iter().map(|r| r.travel_time).sum();
+        Solution {
+            instance_name: t_data.instance_name.clone(),
+            feasible: rest.feasible && routes.iter().all(|r| r.violations.is_empty()),
+            routes,
+            objective,
+            fitness: None,
+            benchmark: t_data.known_benchmark(),
+            benchmark_gap: solution::benchmark_gap(objective, t_data),
+            seed: None,
+            run_time_secs: 0.0,
+            objectives: None,
+            unassigned: rest.unassigned.iter().map(|&gene| self.patient(gene)).collect(),
+            drop_cost: rest.drop_cost,
+        }
+    }
+
+    fn merge_route(&self, part: &FixedPart, mut route: NurseRoute, t_data: &TrainData) -> NurseRoute {
+        let shift = t_data.nurse(part.route.nurse);
+        let duration = if !route.visits.is_empty() {
+            route.duration + self.data.nurse(part.route.nurse).shift_start - shift.shift_start
+        } else if !part.route.visits.is_empty() {
+            // A nurse without unfixed visits still has to get back
+            let travel_time = t_data.travel_time(part.location, shift.end_location, part.free);
+            route.travel_time += travel_time;
+            if part.free + travel_time > shift.shift_end {
+                route.violations.push(Violation::DepotReturn { minutes: part.free + travel_time - shift.shift_end });
+            }
+            part.free + travel_time - shift.shift_start
+        } else {
+            part.route.duration
+        };
+        let fixed_visits = part.route.visits.len();
+        for visit in route.visits.iter_mut() {
+            visit.patient = self.patient(visit.patient);
+        }
+        NurseRoute {
+            nurse: part.route.nurse,
+            visits: part.route.visits.iter().cloned().chain(route.visits).collect(),
+            load: part.route.load + route.load,
+            travel_time: part.route.travel_time + route.travel_time,
+            duration,
+            window_cost: part.route.window_cost + route.window_cost,
+            continuity_cost: part.route.continuity_cost + route.continuity_cost,
+            rest_break: part.route.rest_break.or(route.rest_break.map(|b| RestBreak { after_visits: b.after_visits + fixed_visits, ..b })),
+            violations: part.route.violations.iter().cloned().chain(route.violations.into_iter().map(|v| self.map_violation(v))).collect(),
+        }
+    }
+
+    /// Patient of the full instance visited by a gene of the sub-instance
+    fn patient(&self, gene: u16) -> u16 {
+        self.patients[gene as usize - 1]
+    }
+
+    fn map_violation(&self, violation: Violation) -> Violation {
+        match violation {
+            Violation::Late { patient, minutes } => Violation::Late { patient: self.patient(patient), minutes },
+            Violation::Unqualified { patient } => Violation::Unqualified { patient: self.patient(patient) },
+            Violation::Synchronization { patients, minutes } => Violation::Synchronization { patients: patients.map(|p| self.patient(p)), minutes },
+            Violation::Precedence { first, then, minutes } => Violation::Precedence { first: self.patient(first), then: self.patient(then), minutes },
+            other => other,
+        }
+    }
+}
+
+impl FixedPart {
+    /// The first `fixed` visits of `route`. The break counts as taken if it is among them,
+    /// or right after them and started by `clock`
+    fn new(route: &NurseRoute, fixed: usize, clock: f64, t_data: &TrainData) -> Self {
+        let shift = t_data.nurse(route.nurse);
+        let visits = &route.visits[..fixed];
+        let rest_break = route.rest_break.filter(|b| b.after_visits < fixed || (b.after_visits == fixed && b.start <= clock));
+
+        let mut travel_time = 0.0;
+        let mut location = shift.start_location;
+        let mut time = shift.shift_start;
+        for (index, visit) in visits.iter().enumerate() {
+            if let Some(b) = rest_break.filter(|b| b.after_visits == index) {
+                time = b.end;
+            }
+            travel_time += t_data.travel_time(location, visit.patient as usize, time);
+            location = visit.patient as usize;
+            time = visit.departure;
+        }
+        let free = time.max(clock).max(rest_break.map_or(f64::NEG_INFINITY, |b| b.end));
+        let is_fixed = |patient: u16| visits.iter().any(|v| v.patient == patient);
+
+        Self {
+            route: NurseRoute {
+                nurse: route.nurse,
+                visits: visits.to_vec(),
+                load: visits.iter().map(|v| t_data.patient(v.patient).demand).sum(),
+                travel_time,
+                duration: if visits.is_empty() && rest_break.is_none() { 0.0 } else { free - shift.shift_start },
+                window_cost: visits.iter().map(|v| t_data.patient(v.patient).window_cost(v.start, v.departure)).sum(),
+                continuity_cost: visits.iter().map(|v| v.continuity_cost).sum(),
+                rest_break,
+                violations: route.violations.iter()
+                    .filter(|v| matches!(v, Violation::Late { patient, .. } | Violation::Unqualified { patient } if is_fixed(*patient)))
+                    .cloned()
+                    .collect(),
+            },
+            location,
+            free,
+        }
+    }
+}
+
+/// Plans again what is left of `solution` after `disruption`, starting from the previous plan.
+/// Runs the GA with `config` for its `reoptimize_generations` on the sub-instance, minimizing its objective at fixed weights,
+/// and returns the best plan of the full instance
+pub fn reoptimize(rng: &mut dyn RngCore, t_data: &TrainData, solution: &Solution, disruption: &Disruption, config: &GaConfig) -> Result<Solution, ParseError> {
+    let sub = SubInstance::new(t_data, solution, disruption)?;
+    let seed = sub.seed_chromosome();
+    if sub.patients.is_empty() {
+        return Ok(sub.merge(&seed, t_data));
+    }
+
+    let fitness_function = ObjectiveFitness::new(&sub.data, config.objective, false);
+    let mut population = pop_init::init_pop_random::<Route, _>(&sub.data, config.reoptimize_pop_size - 1, &fitness_function, rng);
+    let (fitness, breaks) = fitness_function.calculate_fitness(&seed);
+    population.push(Route::create(seed, fitness, breaks <= 0.0));
+    let mutations = crate::mutation_vec(rng, &sub.data);
+    let mut gen_alg = GenAlg::new(
+        ElitismSurvivorSelection::new(),
+        TournamentParentSelection::new(config.tournament_size),
+        OrderOneCrossover::new(),
+        fitness_function,
+        &sub.data, mutations,
+    ).with_config(config.clone());
+    for _ in 0..config.reoptimize_generations {
+        population = gen_alg.evolve(rng, &population);
+    }
+    Ok(sub.merge(helper::best_fitness(&population).chromosome(), t_data))
+}
@@ -0,0 +1,92 @@
+//! Monte Carlo evaluation of how a plan holds up when travel and care take longer or shorter than planned.
+//! Every sample draws all travel and care times from the instance's [`Uncertainty`] and replays the nurse routes,
+//! keeping the visits and the break position of the deterministic schedule.
+//! Synchronized and precedence-constrained visits do not wait for each other in the samples.
+
+use std::fmt;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use crate::evaluator::RouteEvaluator;
+use crate::parsing::{Nurse, TrainData, Uncertainty};
+
+#[derive(Debug, Clone)]
+pub struct RobustnessReport {
+    pub samples: usize,
+    /// Share of samples in which care of a patient finishes after its end_time, indexed by gene
+    pub late_probability: Vec<f64>,
+    /// Mean minutes every nurse is back after the end of the shift
+    pub expected_overtime: Vec<f64>,
+}
+
+impl RobustnessReport {
+    pub fn expected_late_visits(&self) -> f64 {
+        self.late_probability.iter().sum()
+    }
+
+    pub fn total_expected_overtime(&self) -> f64 {
+        self.expected_overtime.iter().sum()
+    }
+}
+
+/// Simulates the nurse routes `uncertainty.samples` times, the same samples for the same seed
+pub fn evaluate<R: AsRef<[u16]>>(routes: &[R], t_data: &TrainData, uncertainty: &Uncertainty) -> RobustnessReport {
+    let evaluator = RouteEvaluator::new(t_data);
+    let breaks: Vec<Option<usize>> = routes.iter().enumerate()
+        .map(|(nurse, route)| evaluator.evaluate_route(nurse, route.as_ref()).break_at.map(|(after, _)| after))
+        .collect();
+    let mut rng = ChaCha8Rng::seed_from_u64(uncertainty.seed);
+    let mut late = vec![0usize; t_data.nbr_patients() + 1];
+    let mut overtime = vec![0.0; routes.len()];
+
+    for _ in 0..uncertainty.samples {
+        for (nurse, route) in routes.iter().enumerate().filter(|(_, r)| !r.as_ref().is_empty()) {
+            let route = route.as_ref();
+            let shift = t_data.nurse(nurse);
+            let mut time = shift.shift_start;
+            let mut location = shift.start_location;
+            for (index, &gene) in route.iter().enumerate() {
+                if breaks[nurse] == Some(index) {
+                    rest(shift, &mut time);
+                }
+                let patient = t_data.patient(gene);
+                let arrival = time + uncertainty.travel.sample(&mut rng, t_data.travel_time(location, gene as usize, time));
+                time = patient.care_start(arrival) + uncertainty.care.sample(&mut rng, patient.care_time);
+                late[gene as usize] += (time > patient.end_time) as usize;
+                location = gene as usize;
+            }
+            if breaks[nurse] == Some(route.len()) {
+                rest(shift, &mut time);
+            }
+            time += uncertainty.travel.sample(&mut rng, t_data.travel_time(location, shift.end_location, time));
+            overtime[nurse] += (time - shift.shift_end).max(0.0);
+        }
+    }
+
+    let samples = uncertainty.samples as f64;
+    RobustnessReport {
+        samples: uncertainty.samples,
+        late_probability: late.iter().map(|&count| count as f64 / samples).collect(),
+        expected_overtime: overtime.iter().map(|minutes| minutes / samples).collect(),
+    }
+}
+
+/// Takes the nurse's break at `time`, or when its window opens
+fn rest(nurse: &Nurse, time: &mut f64) {
+    if let Some(rule) = nurse.break_rule {
+        *time = time.max(rule.start) + rule.duration;
+    }
+}
+
+impl fmt::Display for RobustnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Robustness over {} samples: expected late visits {:.2}, expected overtime {:.2}",
+                 self.samples, self.expected_late_visits(), self.total_expected_overtime())?;
+        for (patient, probability) in self.late_probability.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+            writeln!(f, "Patient {} late with probability {:.2}", patient, probability)?;
+        }
+        for (nurse, minutes) in self.expected_overtime.iter().enumerate().filter(|(_, &m)| m > 0.0) {
+            writeln!(f, "Nurse {:0>2} expected back {:.2} late", nurse, minutes)?;
+        }
+        Ok(())
+    }
+}
@@ -18,7 +18,7 @@ where I: Individual
     //println!("{:?}", b1.fitness());
    // println!("{:?}", b2.fitness());
 
-    if rng.gen_bool(p1 as f64) {0} else {1}
+    if rng.gen_bool(p1) {0} else {1}
 }
 
 pub fn boltzmann_operator<I>(current: &I, neighbor: &I, fmax: f64, favg: f64) -> f64
@@ -26,16 +26,29 @@ where I: Individual
 {
     let k:f64 = 0.3;
     let delta_f = neighbor.fitness() - current.fitness();
-    let prob =  if OrderedFloat(delta_f) > OrderedFloat(0.0) {1f64} else {f64::powf(std::f64::consts::E, (k * delta_f) / (fmax - favg))};
-    prob
+    if OrderedFloat(delta_f) > OrderedFloat(0.0) {1f64} else {f64::powf(std::f64::consts::E, (k * delta_f) / (fmax - favg))}
 }
 
 pub trait SurvivorSelection {
     fn select<I>(&self, rng: &mut dyn RngCore, population: &mut Vec<I>, pop_size: usize)
         where I: Individual;
+
+    /// Whether GenAlg lets every child compete with its closer parent by fitness before survivor selection
+    fn crowding_replacement(&self) -> bool {
+        true
+    }
+
+    /// Whether GenAlg lets the parents compete with their offspring in survivor selection
+    fn keeps_parents(&self) -> bool {
+        false
+    }
 }
 
 pub trait ParentSelection {
+    /// Called by GenAlg with the parents of a generation before selecting from them
+    fn prepare<I>(&mut self, _population: &[I])
+        where I: Individual {}
+
     fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) ->  [&'a I; 2]
         where I: Individual;
 }
@@ -61,6 +74,39 @@ impl ParentSelection for TournamentParentSelection {
     }
 }
 
+/// Tournaments by the crowded comparison of NSGA-II: lower front first, then larger crowding distance
+pub struct CrowdedTournamentSelection {
+    tournament_size: usize,
+    /// Front and crowding distance of every individual of the prepared population
+    rank: Vec<(usize, f64)>,
+}
+impl CrowdedTournamentSelection {
+    pub fn new(tournament_size: usize) -> Self {
+        Self { tournament_size, rank: Vec::new() }
+    }
+}
+impl ParentSelection for CrowdedTournamentSelection {
+    fn prepare<I>(&mut self, population: &[I]) where I: Individual {
+        self.rank = vec![(0, 0.0); population.len()];
+        for (front_index, front) in non_dominated_sort(population).iter().enumerate() {
+            for (&i, distance) in front.iter().zip(crowding_distance(population, front)) {
+                self.rank[i] = (front_index, distance);
+            }
+        }
+    }
+
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> [&'a I; 2] where I: Individual {
+        assert!(population.len() >= (2 * self.tournament_size));
+        assert_eq!(population.len(), self.rank.len(), "population not prepared");
+
+        let sample = (0..population.len()).choose_multiple(rng, 2 * self.tournament_size);
+        let crowded = |&a: &usize, &b: &usize| self.rank[a].0.cmp(&self.rank[b].0).then(self.rank[b].1.total_cmp(&self.rank[a].1));
+        let winner1 = sample[..self.tournament_size].iter().min_by(|a, b| crowded(a, b)).unwrap();
+        let winner2 = sample[self.tournament_size..].iter().min_by(|a, b| crowded(a, b)).unwrap();
+
+        [&population[*winner1], &population[*winner2]]
+    }
+}
 
 pub struct RouletteParentSelection;
 
@@ -103,7 +149,7 @@ impl ElitismSurvivorSelection {
 
 impl SurvivorSelection for ElitismSurvivorSelection {
     fn select<I>(&self, rng: &mut dyn RngCore, population: &mut Vec<I>, pop_size: usize) where I: Individual {
-        population.sort_by(|a, b| OrderedFloat(a.fitness()).cmp(&OrderedFloat(b.fitness())));
+        population.sort_by_key(|a| OrderedFloat(a.fitness()));
         population.truncate(pop_size)
 
     }
@@ -119,12 +165,136 @@ impl ElitismSurvivorSelectionKeepFeasible {
 
 impl SurvivorSelection for ElitismSurvivorSelectionKeepFeasible {
     fn select<I>(&self, rng: &mut dyn RngCore, population: &mut Vec<I>, pop_size: usize) where I: Individual {
-        population.sort_by(|a, b| OrderedFloat(a.fitness()).cmp(&OrderedFloat(b.fitness())));
-        let mut t: Vec<I> = population.drain(..pop_size).into_iter().collect();
+        population.sort_by_key(|a| OrderedFloat(a.fitness()));
+        let mut t: Vec<I> = population.drain(..pop_size).collect();
         t.retain(|f| f.feasible());
-        t.sort_by(|a, b| OrderedFloat(a.fitness()).cmp(&OrderedFloat(b.fitness())));
+        t.sort_by_key(|a| OrderedFloat(a.fitness()));
         population.append(&mut t);
         let _ = population.drain(population.len() - pop_size..);
 
     }
 }
+
+/// NSGA-II: fills the population front by front of non-dominated sorting,
+/// the last front that does not fit entirely is cut by crowding distance
+pub struct NsgaIISurvivorSelection;
+
+impl NsgaIISurvivorSelection {
+    pub fn new() -> Self  {
+        Self
+    }
+}
+
+impl SurvivorSelection for NsgaIISurvivorSelection {
+    fn select<I>(&self, _rng: &mut dyn RngCore, population: &mut Vec<I>, pop_size: usize) where I: Individual {
+        let mut keep: Vec<usize> = Vec::with_capacity(pop_size);
+        for front in non_dominated_sort(population) {
+            if keep.len() + front.len() <= pop_size {
+                keep.extend(front);
+                continue;
+            }
+            let distance = crowding_distance(population, &front);
+            let mut order: Vec<usize> = (0..front.len()).collect();
+            order.sort_by(|&a, &b| distance[b].total_cmp(&distance[a]));
+            keep.extend(order.iter().take(pop_size - keep.len()).map(|&i| front[i]));
+            break;
+        }
+
+        let mut slots: Vec<Option<I>> = population.drain(..).map(Some).collect();
+        population.extend(keep.iter().map(|&i| slots[i].take().unwrap()));
+    }
+
+    /// Offspring are ranked by their objectives here, not against a parent by fitness
+    fn crowding_replacement(&self) -> bool {
+        false
+    }
+
+    /// Elitism of NSGA-II, a non-dominated parent is only replaced by offspring dominating or crowding it out
+    fn keeps_parents(&self) -> bool {
+        true
+    }
+}
+
+/// Individuals without objectives compare by fitness only, GenAlg gives multi-objective individuals theirs
+pub fn dominates<I>(a: &I, b: &I) -> bool
+where I: Individual
+{
+    match (a.objectives(), b.objectives()) {
+        (Some(a), Some(b)) => a.dominates(b),
+        _ => a.fitness() < b.fitness(),
+    }
+}
+
+fn objective_values<I>(individual: &I) -> Vec<f64>
+where I: Individual
+{
+    match individual.objectives() {
+        Some(objectives) => objectives.values().to_vec(),
+        None => vec![individual.fitness()],
+    }
+}
+
+/// Indices of the population grouped into fronts, the first front is non-dominated
+pub fn non_dominated_sort<I>(population: &[I]) -> Vec<Vec<usize>>
+where I: Individual
+{
+    let mut dominated_by: Vec<usize> = vec![0; population.len()];
+    let mut dominates_over: Vec<Vec<usize>> = vec![Vec::new(); population.len()];
+    for a in 0..population.len() {
+        for b in (a + 1)..population.len() {
+            if dominates(&population[a], &population[b]) {
+                dominates_over[a].push(b);
+                dominated_by[b] += 1;
+            } else if dominates(&population[b], &population[a]) {
+                dominates_over[b].push(a);
+                dominated_by[a] += 1;
+            }
+        }
+    }
+
+    let mut fronts: Vec<Vec<usize>> = Vec::new();
+    let mut front: Vec<usize> = (0..population.len()).filter(|&i| dominated_by[i] == 0).collect();
+    while !front.is_empty() {
+        let mut next: Vec<usize> = Vec::new();
+        for &i in front.iter() {
+            for &j in dominates_over[i].iter() {
+                dominated_by[j] -= 1;
+                if dominated_by[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        fronts.push(front);
+        front = next;
+    }
+    fronts
+}
+
+/// Crowding distance of every member of `front`, in the same order. Boundary members get infinity
+pub fn crowding_distance<I>(population: &[I], front: &[usize]) -> Vec<f64>
+where I: Individual
+{
+    let values: Vec<Vec<f64>> = front.iter().map(|&i| objective_values(&population[i])).collect();
+    let mut distance = vec![0.0f64; front.len()];
+    if front.len() <= 2 {
+        return vec![f64::INFINITY; front.len()];
+    }
+
+    let columns: Vec<Vec<f64>> = (0..values[0].len()).map(|o| values.iter().map(|v| v[o]).collect()).collect();
+    for column in columns.iter() {
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|&a, &b| column[a].total_cmp(&column[b]));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        let range = column[last] - column[first];
+        // An objective the whole front shares, e.g. drop cost without optional patients, has no boundary
+        if range <= 0.0 {
+            continue;
+        }
+        distance[first] = f64::INFINITY;
+        distance[last] = f64::INFINITY;
+        for w in order.windows(3) {
+            distance[w[1]] += (column[w[2]] - column[w[0]]) / range;
+        }
+    }
+    distance
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use crate::evaluator::{self, Objectives, PenaltyConfig, RouteEvaluator, RouteStats, TimingViolation, VisitTimes};
+use crate::helper;
+use crate::individual::chromosome::Chromosome;
+use crate::parsing::TrainData;
+
+/// Times of a single patient visit. The nurse waits between arrival and start if early
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    pub patient: u16,
+    pub arrival: f64,
+    pub start: f64,
+    pub departure: f64,
+    /// Nurse that visited the patient in the continuity reference, if there is one
+    #[serde(default)]
+    pub previous_nurse: Option<usize>,
+    /// Cost of visiting the patient with another nurse than in the reference
+    #[serde(default)]
+    pub continuity_cost: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    /// Care finished `minutes` after the end_time of a hard window
+    Late { patient: u16, minutes: f64 },
+    Overload { amount: i32 },
+    /// Nurse back at the depot `minutes` after return time
+    DepotReturn { minutes: f64 },
+    /// Patient needs a skill the nurse does not have
+    Unqualified { patient: u16 },
+    /// Visits for two nurses starting `minutes` apart, or on one route
+    Synchronization { patients: [u16; 2], minutes: f64 },
+    /// `then` starting `minutes` outside the allowed gap after `first`
+    Precedence { first: u16, then: u16, minutes: f64 },
+    /// Break starting `minutes` after the latest start its window allows
+    LateBreak { minutes: f64 },
+    /// Worked `minutes` longer than allowed without a break
+    WorkingTime { minutes: f64 },
+}
+
+/// Break of a nurse, taken after the first `after_visits` visits of the route
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RestBreak {
+    pub after_visits: usize,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Schedule of one nurse, `duration` is from shift start until back at the end location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NurseRoute {
+    pub nurse: usize,
+    pub visits: Vec<Visit>,
+    pub load: i32,
+    pub travel_time: f64,
+    pub duration: f64,
+    /// Earliness and tardiness cost of soft time windows
+    #[serde(default)]
+    pub window_cost: f64,
+    /// Cost of the patients with another nurse than in the continuity reference
+    #[serde(default)]
+    pub continuity_cost: f64,
+    #[serde(default)]
+    pub rest_break: Option<RestBreak>,
+    pub violations: Vec<Violation>,
+}
+
+/// Machine-readable solution, written as json next to the text outputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Solution {
+    pub instance_name: String,
+    pub routes: Vec<NurseRoute>,
+    /// Total travel time
+    pub objective: f64,
+    /// Fitness the producing run scored the solution with, see [`Solution::with_fitness`].
+    /// Missing when the routes were not evaluated as a whole
+    #[serde(default)]
+    pub fitness: Option<f64>,
+    pub feasible: bool,
+    /// Best known objective of the instance, missing when it has none
+    pub benchmark: Option<f32>,
+    /// Percent above benchmark, missing when the instance has no benchmark
+    pub benchmark_gap: Option<f64>,
+    pub seed: Option<u64>,
+    pub run_time_secs: f64,
+    #[serde(default)]
+    pub objectives: Option<Objectives>,
+    /// Patients left unvisited and the sum of their drop costs
+    #[serde(default)]
+    pub unassigned: Vec<u16>,
+    #[serde(default)]
+    pub drop_cost: f64,
+}
+
+/// Non-dominated solutions of a multi-objective run, written as pareto_front.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParetoFront {
+    pub instance_name: String,
+    pub seed: Option<u64>,
+    pub run_time_secs: f64,
+    pub solutions: Vec<Solution>,
+}
+
+/// Plan of a multi-day instance, one single-day solution per day of the horizon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeekSolution {
+    pub instance_name: String,
+    /// Total travel time over all days
+    pub objective: f64,
+    pub feasible: bool,
+    pub seed: Option<u64>,
+    pub run_time_secs: f64,
+    /// Days each patient is visited, patient i at index i - 1
+    pub patient_days: Vec<Vec<u32>>,
+    pub days: Vec<Solution>,
+}
+
+/// Nurse routes read back from a solution file, possibly written by an older run or another solver
+#[derive(Debug, Clone)]
+pub struct RoutePlan {
+    pub instance_name: Option<String>,
+    pub routes: Vec<Vec<u16>>,
+    /// Fitness the producing run reported, if the format has one
+    pub reported_fitness: Option<f64>,
+}
+
+impl Solution {
+    pub fn route_plan(&self) -> RoutePlan {
+        RoutePlan {
+            instance_name: Some(self.instance_name.clone()),
+            routes: self.routes.iter().map(|r| r.visits.iter().map(|v| v.patient).collect()).collect(),
+            reported_fitness: self.fitness,
+        }
+    }
+
+    /// Builds the per-visit schedule of a delimited chromosome, with the fitness of [`evaluator::fitness`] at the default weights.
+    /// Unassigned patients without a drop cost make the solution infeasible
+    pub fn from_chromosome(chromosome: &Chromosome, t_data: &TrainData) -> Self {
+        let evaluator = RouteEvaluator::new(t_data);
+        let (nurse_routes, unassigned) = helper::split_unassigned(chromosome, t_data);
+        let schedules = evaluator.schedule_routes(&nurse_routes);
+        let timing = evaluator.timing_violations(&schedules);
+        let (mut route_stats, routes): (Vec<RouteStats>, Vec<NurseRoute>) = schedules.into_iter()
+            .enumerate()
+            .map(|(nurse, (stats, visit_times))| schedule_route(nurse, stats, &visit_times, &timing, t_data))
+            .unzip();
+        let pool = t_data.pool_route().map(|pool| evaluator.evaluate_route(pool, &unassigned));
+        route_stats.extend(pool);
+
+        let objective: f64 = routes.iter().map(|r| r.travel_time).sum();
+
+        Self {
+            instance_name: t_data.instance_name.clone(),
+            feasible: routes.iter().all(|r| r.violations.is_empty()) && pool.is_none_or(|p| p.missed_visits == 0),
+            routes,
+            objective,
+            fitness: Some(evaluator::fitness(&route_stats, &PenaltyConfig::default()).0),
+            benchmark: t_data.known_benchmark(),
+            benchmark_gap: benchmark_gap(objective, t_data),
+            seed: None,
+            run_time_secs: 0.0,
+            objectives: Some(Objectives::from_routes(&route_stats)),
+            unassigned,
+            drop_cost: pool.map_or(0.0, |p| p.drop_cost),
+        }
+    }
+
+    /// Replaces the fitness, e.g. with the value of the run's own fitness function
+    pub fn with_fitness(mut self, fitness: f64) -> Self {
+        self.fitness = Some(fitness);
+        self
+    }
+}
+
+/// Percent `objective` is above the instance's benchmark, None without a benchmark
+pub fn benchmark_gap(objective: f64, t_data: &TrainData) -> Option<f64> {
+    t_data.known_benchmark().map(|benchmark| (objective - benchmark as f64) / benchmark as f64 * 100.0)
+}
+
+fn schedule_route(nurse: usize, stats: RouteStats, visit_times: &[VisitTimes], timing: &[TimingViolation], t_data: &TrainData) -> (RouteStats, NurseRoute) {
+
+    let mut violations: Vec<Violation> = visit_times.iter()
+        .filter(|v| t_data.patient(v.patient).has_hard_window() && v.departure > t_data.patient(v.patient).end_time)
+        .map(|v| Violation::Late { patient: v.patient, minutes: v.departure - t_data.patient(v.patient).end_time })
+        .collect();
+    violations.extend(visit_times.iter()
+        .filter(|v| !t_data.qualified(nurse, v.patient))
+        .map(|v| Violation::Unqualified { patient: v.patient }));
+    violations.extend(timing.iter().filter(|t| t.route() == nurse).map(|t| match *t {
+        TimingViolation::Synchronized { patients, minutes, .. } => Violation::Synchronization { patients, minutes },
+        TimingViolation::Precedence { first, then, minutes, .. } => Violation::Precedence { first, then, minutes },
+    }));
+    if stats.overload > 0 {
+        violations.push(Violation::Overload { amount: stats.overload });
+    }
+    if stats.depot_overtime > 0.0 {
+        violations.push(Violation::DepotReturn { minutes: stats.depot_overtime });
+    }
+    if stats.late_break > 0.0 {
+        violations.push(Violation::LateBreak { minutes: stats.late_break });
+    }
+    if stats.overwork > 0.0 {
+        violations.push(Violation::WorkingTime { minutes: stats.overwork });
+    }
+
+    let visits = visit_times.iter()
+        .map(|v| Visit {
+            patient: v.patient,
+            arrival: v.arrival,
+            start: v.start,
+            departure: v.departure,
+            previous_nurse: t_data.continuity().and_then(|c| c.previous_nurse(v.patient)),
+            continuity_cost: t_data.continuity().map_or(0.0, |c| c.cost(nurse, v.patient)),
+        })
+        .collect();
+    let route = NurseRoute {
+        nurse,
+        visits,
+        load: stats.load,
+        travel_time: stats.travel_time,
+        duration: stats.duration,
+        window_cost: stats.window_cost,
+        continuity_cost: stats.continuity_cost,
+        rest_break: stats.break_at.zip(t_data.nurse(nurse).break_rule)
+            .map(|((after_visits, start), rule)| RestBreak { after_visits, start, end: start + rule.duration }),
+        violations,
+    };
+    (stats, route)
+}
@@ -1,67 +1,1003 @@
 use crate::individual::chromosome::Chromosome;
-use std::collections::BTreeMap;
-use std::iter::FromIterator;
-use ordered_float::OrderedFloat;
 use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 use crate::crossover::Crossover;
-use crate::gen_alg::{RouletteSelection, Selection};
+use crate::fitness::FitnessFunction;
+use crate::individual::individual::Individual;
 use crate::helper::hamming_distance;
-use crate::individual::individual::{calculate_fitness, Route};
+use crate::individual::individual::{calculate_fitness, MultiObjectiveRoute, Route};
 use crate::mutation::Mutation;
 use crate::population_init::individual_init::{random_chromo, random_chromo_no_delimit, random_route};
-use crate::selection::{general_crowding, ParentSelection};
+use crate::selection::ParentSelection;
+use crate::selection::SurvivorSelection;
 use super::*;
+use crate::error::ParseError;
 
-pub fn valid_solution() {
-    todo!();
+/// Every patient is visited exactly once, by at most nbr_nurses nurses
+pub fn valid_solution(routes: &[Vec<u16>], data: &parsing::TrainData) {
+    let report = checker::check_routes(routes, data);
+    assert!(report.is_complete(), "{}", report);
 }
 
-pub fn valid_chromosome(chromo: &Chromosome) {
+pub fn valid_chromosome(chromo: &Chromosome, data: &parsing::TrainData) {
     let v = chromo.iter().filter(|&n| *n == 0).count();
-    assert_eq!(v, 24);
+    assert_eq!(v, data.nbr_delimiters());
 
-    for i in 1..101
+    for i in 1..(data.nbr_patients() + 1) as u16
     {
         let t = chromo.iter().filter(|&n| *n == i).count();
         assert_eq!(t, 1);
     }
+    assert!(helper::valid_chromosome(chromo, data));
 }
 
-pub fn valid_chromosome_nurseless(chromo: &Chromosome)
+pub fn valid_chromosome_nurseless(chromo: &Chromosome, data: &parsing::TrainData)
 {
-    for i in 1..101
+    assert_eq!(chromo.len(), data.nbr_patients());
+    for i in 1..(data.nbr_patients() + 1) as u16
     {
         let t = chromo.iter().filter(|&n| *n == i).count();
         assert_eq!(t, 1);
     }
 }
 
+/// Cuts an instance down to its first `num_patients` patients and `num_nurses` nurses
+pub fn shrink_instance(data: &parsing::TrainData, num_patients: usize, num_nurses: i32) -> parsing::TrainData
+{
+    let mut small = data.clone();
+    small.nbr_nurses = num_nurses;
+    small.patients.retain(|id, _| id.parse::<usize>().unwrap() <= num_patients);
+    small.travel_times.truncate(num_patients + 1);
+    for row in small.travel_times.iter_mut()
+    {
+        row.truncate(num_patients + 1);
+    }
+    small.index_patients();
+    small
+}
+
 #[test]
 pub fn point_mean()
 {
-    let mut points: Vec<(u16, u16)> = vec![(2, 2), (8, 4), (5, 9)];
+    let points: Vec<(u16, u16)> = vec![(2, 2), (8, 4), (5, 9)];
     let t = kmeans::point_mean(&points);
     assert_eq!(t, (5.0, 5.0));
 }
 
 
+const SOLOMON_SAMPLE: &str = "C101
+
+VEHICLE
+NUMBER     CAPACITY
+  3         200
+
+CUSTOMER
+CUST NO.  XCOORD.   YCOORD.    DEMAND   READY TIME  DUE DATE   SERVICE   TIME
+
+    0      40         50          0          0       1236          0
+    1      45         68         10        912        967         90
+    2      45         70         30        825        870         90
+    3      42         66         10         65        146         90
+";
+
+#[test]
+pub fn solomon_instance()
+{
+    let data = parsing::parse_solomon(SOLOMON_SAMPLE).unwrap();
+
+    assert_eq!(data.instance_name, "C101");
+    assert_eq!(data.nbr_nurses, 3);
+    assert_eq!(data.capacity_nurse, 200);
+    assert_eq!(data.depot.return_time, 1236.0);
+    assert_eq!(data.nbr_patients(), 3);
+
+    let patient = &data.patients["1"];
+    assert_eq!(patient.start_time, 912.0);
+    assert_eq!(patient.end_time, 967.0 + 90.0);
+    assert_eq!(patient.care_time, 90.0);
+    assert_eq!(patient.demand, 10);
+
+    assert_eq!(data.travel_times.len(), 4);
+    assert_eq!(data.travel_times[0][0], 0.0);
+    assert_eq!(data.travel_times[1][2], data.travel_times[2][1]);
+    assert!(approx::relative_eq!(data.travel_times[0][3], 16.1245, epsilon = 1e-4));
+
+    // No benchmark, so no gap to report
+    assert_eq!(data.known_benchmark(), None);
+    let solution = solution::Solution::from_chromosome(&helper::combine_into_chromo(&[vec![1, 2], vec![3], vec![]]), &data);
+    assert_eq!(solution.benchmark, None);
+    assert_eq!(solution.benchmark_gap, None);
+
+    // A customer row where the depot should be is rejected
+    let shifted = SOLOMON_SAMPLE.replace("    0      40         50          0          0       1236          0\n", "");
+    assert!(matches!(parsing::parse_solomon(&shifted), Err(ParseError::InvalidField { field: "depot", .. })));
+}
+
+#[test]
+pub fn instance_format_detection()
+{
+    let solomon_path = std::env::temp_dir().join("bio_ai_solomon_sample.txt");
+    std::fs::write(&solomon_path, SOLOMON_SAMPLE).unwrap();
+    let solomon = parsing::parse_instance(solomon_path.to_str().unwrap()).unwrap();
+    assert_eq!(solomon.nbr_patients(), 3);
+
+    let json = parsing::parse_instance("train/train_0.json").unwrap();
+    assert_eq!(json.instance_name, "train_0");
+}
+
+/// Loads train_0 as json so single fields can be broken before parsing
+fn train_0_json() -> serde_json::Value
+{
+    serde_json::from_str(&std::fs::read_to_string("train/train_0.json").unwrap()).unwrap()
+}
+
+#[test]
+pub fn parse_errors()
+{
+    assert!(matches!(parsing::parse_json("train/does_not_exist.json"), Err(ParseError::Read { .. })));
+    assert!(matches!(parsing::parse_json_str("{ not json"), Err(ParseError::Json(_))));
+
+    let mut json = train_0_json();
+    json["travel_times"][17].as_array_mut().unwrap().pop();
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::NonSquareMatrix { row: 17, expected: 101, found: 100 })));
+
+    let mut json = train_0_json();
+    json["travel_times"].as_array_mut().unwrap().pop();
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::MatrixRows { expected: 101, found: 100 })));
+
+    let mut json = train_0_json();
+    let patient = json["patients"].as_object_mut().unwrap().remove("42").unwrap();
+    json["patients"].as_object_mut().unwrap().insert("101".to_string(), patient);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::MissingPatient { id: 42 })));
+
+    let mut json = train_0_json();
+    json["patients"]["7"]["care_time"] = serde_json::json!(-5);
+    match parsing::parse_json_str(&json.to_string()) {
+        Err(ParseError::NegativeValue { patient, field, .. }) => assert_eq!((patient.as_str(), field), ("7", "care_time")),
+        other => panic!("expected negative care_time, got {:?}", other.map(|d| d.instance_name)),
+    }
+
+    let broken_row = SOLOMON_SAMPLE.replace("    2      45         70         30", "    2      45         70         xx");
+    assert!(matches!(parsing::parse_solomon(&broken_row), Err(ParseError::InvalidRow { line: 12, .. })));
+}
+
+#[test]
+pub fn patient_table()
+{
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+
+    for (id, patient) in data.patients.iter()
+    {
+        let gene: u16 = id.parse().unwrap();
+        assert_eq!(data.patient(gene).start_time, patient.start_time);
+        assert_eq!(data.patient(gene).end_time, patient.end_time);
+        assert_eq!(data.patient_by_id(id).unwrap().care_time, patient.care_time);
+    }
+    assert!(data.patient_by_id("0").is_none());
+    assert!(data.patient_by_id("101").is_none());
+    assert!(data.patient_by_id("nurse").is_none());
+}
+
+#[test]
+pub fn validate_instances()
+{
+    for i in 0..10
+    {
+        let data = parsing::parse_json(&format!("train/train_{}.json", i)).unwrap();
+        let errors: Vec<_> = data.validate().into_iter().filter(|d| d.is_error()).collect();
+        assert!(errors.is_empty(), "train_{}: {:?}", i, errors);
+    }
+
+    let mut data = parsing::parse_json("train/train_0.json").unwrap();
+    data.patients.get_mut("3").unwrap().start_time = 2000.0;
+    data.patients.get_mut("5").unwrap().care_time = 1000.0;
+    data.patients.get_mut("9").unwrap().demand = 500;
+    data.travel_times[1][2] = 500.0;
+    let issues: Vec<validation::Issue> = data.validate().into_iter().map(|d| d.issue).collect();
+
+    assert!(issues.iter().any(|i| matches!(i, validation::Issue::EmptyWindow { patient: 3, .. })));
+    assert!(issues.iter().any(|i| matches!(i, validation::Issue::CareExceedsWindow { patient: 5, .. })));
+    assert!(issues.iter().any(|i| matches!(i, validation::Issue::UnreachableWindow { patient: 5, .. })));
+    assert!(issues.iter().any(|i| matches!(i, validation::Issue::DemandExceedsCapacity { patient: 9, .. })));
+    assert!(issues.iter().any(|i| matches!(i, validation::Issue::TriangleInequality { from: 1, to: 2, .. })));
+
+    // Large instances are checked too, with a count instead of every pair
+    let patient = data.patients["1"].clone();
+    let size = 400;
+    for id in data.nbr_patients() + 1..size {
+        data.patients.insert(id.to_string(), patient.clone());
+    }
+    data.travel_times = vec![vec![1.0; size]; size];
+    data.travel_times[1][2] = 500.0;
+    data.reindex().unwrap();
+    for to in 3..10
+    {
+        data.travel_times[1][to] = 100.0 + to as f64;
+    }
+    let issues: Vec<validation::Issue> = data.validate().into_iter().map(|d| d.issue).collect();
+    assert!(issues.contains(&validation::Issue::TriangleInequalities { pairs: 8, reported: 5 }));
+    let listed: Vec<usize> = issues.iter().filter_map(|i| match i {
+        validation::Issue::TriangleInequality { to, .. } => Some(*to),
+        _ => None,
+    }).collect();
+    assert_eq!(listed, vec![2, 9, 8, 7, 6]);
+}
+
+#[test]
+pub fn solution_json()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let chromo = random_chromo(&mut rng, &data);
+
+    let solution = solution::Solution::from_chromosome(&chromo, &data);
+    assert_eq!(solution.routes.len(), data.nbr_nurses as usize);
+
+    let visited: Vec<u16> = solution.routes.iter().flat_map(|r| r.visits.iter().map(|v| v.patient)).collect();
+    assert_eq!(visited, chromo.iter().filter(|&g| *g != 0).cloned().collect::<Vec<u16>>());
+    for visit in solution.routes.iter().flat_map(|r| r.visits.iter())
+    {
+        assert!(visit.arrival <= visit.start);
+        assert!(approx::relative_eq!(visit.departure, visit.start + data.patient(visit.patient).care_time));
+    }
+    assert!(approx::relative_eq!(solution.objective, solution.routes.iter().map(|r| r.travel_time).sum::<f64>()));
+    assert_eq!(solution.feasible, solution.routes.iter().all(|r| r.violations.is_empty()));
+    // The same fitness as the GA's, the random chromosome breaks constraints
+    let (fitness, _) = evaluator::fitness(&evaluator::RouteEvaluator::new(&data).evaluate_chromosome(&chromo), &evaluator::PenaltyConfig::default());
+    assert!(approx::relative_eq!(solution.fitness.unwrap(), fitness));
+    assert!(solution.feasible || fitness > solution.objective);
+    // Reported as the run scored it
+    let solution = solution.with_fitness(fitness + 1.0);
+    assert!(approx::relative_eq!(solution.fitness.unwrap(), fitness + 1.0));
+    assert!(helper::gen_solution_string(&chromo, &data, fitness + 1.0).contains(&format!("Fitness (travel time with penalties and costs): {:.2}", fitness + 1.0)));
+
+    let json: serde_json::Value = serde_json::to_value(&solution).unwrap();
+    assert_eq!(json["instance_name"], "train_0");
+    assert!(json["routes"][0]["visits"].is_array());
+    assert!(json["benchmark_gap"].is_number());
+
+    // The checker compares against the fitness, not the travel time
+    assert_eq!(solution.route_plan().reported_fitness, solution.fitness);
+    let unscored = solution::Solution { fitness: None, ..solution };
+    assert_eq!(unscored.route_plan().reported_fitness, None);
+}
+
+#[test]
+pub fn read_bracket_solution_file()
+{
+    let data = parsing::parse_json("train/train_2.json").unwrap();
+    let plans = parsing::parse_solution_file("solution.txt").unwrap();
+
+    assert_eq!(plans.len(), 8);
+    for plan in plans.iter()
+    {
+        assert_eq!(plan.instance_name.as_deref(), Some("train_2"));
+        assert_eq!(plan.routes.len(), data.nbr_nurses as usize);
+        assert!(plan.reported_fitness.is_some());
+        valid_solution(&plan.routes, &data);
+    }
+
+    let chromo: Chromosome = vec![3, 0, 0, 1, 2, 0].into_iter().collect();
+    let plans = parsing::parse_solution_str(&format!("{}\n", chromo.format_chromosome())).unwrap();
+    assert_eq!(plans[0].routes, vec![vec![3], vec![], vec![1, 2], vec![]]);
+    assert!(plans[0].instance_name.is_none());
+
+    assert!(matches!(parsing::parse_solution_str("train_2\n[[1,2],[3\n"), Err(ParseError::InvalidRow { line: 2, .. })));
+}
+
+#[test]
+pub fn check_solution_against_schedule()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let chromo = random_chromo(&mut rng, &data);
+
+    let solution = solution::Solution::from_chromosome(&chromo, &data);
+    let plans = parsing::parse_solution_str(&serde_json::to_string(&solution).unwrap()).unwrap();
+    assert_eq!(plans.len(), 1);
+    assert_eq!(plans[0].routes, helper::split_into_nurses(&chromo));
+
+    let report = checker::check_routes(&plans[0].routes, &data);
+    assert!(report.is_complete());
+    assert!(approx::relative_eq!(report.total_travel_time, solution.objective));
+    assert_eq!(report.is_feasible(), solution.feasible);
+    for (nurse, route) in report.nurses.iter().zip(solution.routes.iter())
+    {
+        let late = route.violations.iter().filter(|v| matches!(v, solution::Violation::Late { .. })).count();
+        assert_eq!(nurse.late_visits.len(), late);
+        assert!(approx::relative_eq!(nurse.return_time, route.duration));
+    }
+}
+
+#[test]
+pub fn route_evaluator_matches_checker()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    for instance in ["train/train_0.json", "train/train_2.json", "train/train_7.json"]
+    {
+        let data = parsing::parse_json(instance).unwrap();
+        let evaluator = evaluator::RouteEvaluator::new(&data);
+        for _ in 0..20
+        {
+            let chromo = random_chromo(&mut rng, &data);
+            let stats = evaluator.evaluate_chromosome(&chromo);
+            let report = checker::check_routes(&helper::split_into_nurses(&chromo), &data);
+
+            assert_eq!(stats.len(), report.nurses.len());
+            for (route, nurse) in stats.iter().zip(report.nurses.iter())
+            {
+                assert!(approx::relative_eq!(route.travel_time, nurse.travel_time));
+                assert!(approx::relative_eq!(route.return_time, nurse.return_time));
+                assert_eq!(route.late_visits as usize, nurse.late_visits.len());
+                assert_eq!(route.overload, nurse.overload);
+                assert_eq!(route.is_feasible(), nurse.is_feasible());
+            }
+
+            let (fitness, breaks) = calculate_fitness(&chromo, &data);
+            let violations: u32 = stats.iter().map(|s| s.violations()).sum();
+            assert_eq!(breaks, violations as f64);
+            let penalty = evaluator::PenaltyConfig::default();
+            let weighted: f64 = report.nurses.iter()
+                .map(|n| penalty.lateness * n.late_visits.iter().map(|l| l.finish - l.end_time).sum::<f64>()
+                    + penalty.overload * n.overload as f64
+                    + penalty.depot_return * n.depot_overtime)
+                .sum();
+            assert!(approx::relative_eq!(fitness, report.total_travel_time * (1.0 + penalty.break_factor * breaks) + weighted, max_relative = 1e-9));
+        }
+    }
+}
+
+#[test]
+pub fn last_route_depot_return()
+{
+    let data = parsing::parse_json("train/train_2.json").unwrap();
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+
+    // Every patient on the last nurse is far past the depot return time of 240
+    let mut genes = vec![0u16; data.nbr_delimiters()];
+    genes.extend(1..(data.nbr_patients() + 1) as u16);
+    let chromo: Chromosome = genes.into_iter().collect();
+
+    let stats = evaluator.evaluate_chromosome(&chromo);
+    let last = stats.last().unwrap();
+    assert!(last.depot_overtime > 0.0);
+    assert!(approx::relative_eq!(last.depot_overtime, last.return_time - data.depot.return_time));
+    assert!(stats[..stats.len() - 1].iter().all(|s| s.is_feasible() && s.travel_time == 0.0));
+}
+
+/// train_0 with every patient's time window set to `kind`, parsed from json
+fn with_time_windows(kind: &str, earliness_cost: f64, tardiness_cost: f64) -> parsing::TrainData
+{
+    let mut json = train_0_json();
+    for patient in json["patients"].as_object_mut().unwrap().values_mut()
+    {
+        patient["time_window"] = serde_json::json!(kind);
+        patient["earliness_cost"] = serde_json::json!(earliness_cost);
+        patient["tardiness_cost"] = serde_json::json!(tardiness_cost);
+    }
+    parsing::parse_json_str(&json.to_string()).unwrap()
+}
+
+#[test]
+pub fn soft_time_windows()
+{
+    let hard = parsing::parse_json("train/train_0.json").unwrap();
+    let soft_late = with_time_windows("soft_late", 0.0, 2.0);
+    let soft_both = with_time_windows("soft_both", 1.5, 2.0);
+    assert_eq!(hard.patient(1).time_window, parsing::TimeWindowKind::Hard);
+    assert_eq!(soft_both.patient(1).time_window, parsing::TimeWindowKind::SoftBoth);
+
+    // Patients in id order on five nurses, late and waiting at the same time
+    let routes: Vec<Vec<u16>> = (0..5u16).map(|n| (n * 20 + 1..n * 20 + 21).collect()).collect();
+    let hard_stats = evaluator::RouteEvaluator::new(&hard).evaluate_routes(&routes);
+    assert!(hard_stats.iter().any(|s| s.late_visits > 0));
+    assert!(hard_stats.iter().all(|s| s.window_cost == 0.0));
+
+    let late_stats = evaluator::RouteEvaluator::new(&soft_late).evaluate_routes(&routes);
+    for (h, s) in hard_stats.iter().zip(late_stats.iter())
+    {
+        assert_eq!(s.late_visits, 0);
+        assert_eq!(s.lateness, 0.0);
+        assert!(approx::relative_eq!(s.soft_lateness, h.lateness));
+        assert!(approx::relative_eq!(s.window_cost, 2.0 * h.lateness));
+        assert_eq!(s.waiting_time, h.waiting_time);
+    }
+    let report = checker::check_routes(&routes, &soft_late);
+    assert!(report.nurses.iter().all(|n| n.late_visits.is_empty()));
+    assert!(approx::relative_eq!(report.total_window_cost, late_stats.iter().map(|s| s.window_cost).sum::<f64>()));
+    let (fitness, breaks) = evaluator::fitness(&late_stats, &evaluator::PenaltyConfig::default());
+    let (hard_fitness, hard_breaks) = evaluator::fitness(&hard_stats, &evaluator::PenaltyConfig::default());
+    assert!(breaks < hard_breaks);
+    assert!(fitness != hard_fitness);
+
+    // No waiting at soft-both windows, starting early costs instead
+    let both_stats = evaluator::RouteEvaluator::new(&soft_both).evaluate_routes(&routes);
+    assert!(both_stats.iter().all(|s| s.waiting_time == 0.0 && s.late_visits == 0));
+    assert!(both_stats.iter().any(|s| s.earliness > 0.0));
+    for (s, nurse) in both_stats.iter().zip(checker::check_routes(&routes, &soft_both).nurses.iter())
+    {
+        assert!(approx::relative_eq!(s.window_cost, 1.5 * s.earliness + 2.0 * s.soft_lateness));
+        assert!(approx::relative_eq!(s.window_cost, nurse.window_cost));
+    }
+    let genes: Chromosome = helper::combine_into_chromo(&routes);
+    let solution = solution::Solution::from_chromosome(&genes, &soft_both);
+    assert!(solution.routes.iter().all(|r| r.violations.iter().all(|v| !matches!(v, solution::Violation::Late { .. }))));
+    assert!(helper::gen_solution_string(&genes, &soft_both, solution.fitness.unwrap()).contains("Soft time window cost"));
+
+    let mut json = train_0_json();
+    json["patients"]["3"]["tardiness_cost"] = serde_json::json!(-1.0);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::NegativeValue { field: "tardiness_cost", .. })));
+}
+
+/// train_0 with a heterogeneous fleet: nurse 0 works 100-600 from a home 5 minutes further out than the depot,
+/// with little capacity and a cost, the others work like before
+fn heterogeneous_fleet_json() -> serde_json::Value
+{
+    let mut json = train_0_json();
+    let mut matrix: Vec<Vec<f64>> = serde_json::from_value(json["travel_times"].clone()).unwrap();
+    let home: Vec<f64> = matrix[0].iter().map(|t| t + 5.0).chain(std::iter::once(0.0)).collect();
+    for (row, time) in matrix.iter_mut().zip(home.iter())
+    {
+        row.push(*time);
+    }
+    matrix.push(home);
+    json["travel_times"] = serde_json::json!(matrix);
+
+    let mut nurses = vec![serde_json::json!({"capacity": 50, "shift_start": 100.0, "shift_end": 600.0, "start_location": 101, "end_location": 101, "cost_per_minute": 0.5})];
+    nurses.extend((1..25).map(|_| serde_json::json!({"capacity": json["capacity_nurse"], "shift_end": json["depot"]["return_time"]})));
+    json["nurses"] = serde_json::json!(nurses);
+    json.as_object_mut().unwrap().remove("nbr_nurses");
+    json
+}
+
+#[test]
+pub fn heterogeneous_fleet()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let homogeneous = parsing::parse_json("train/train_0.json").unwrap();
+    assert_eq!(homogeneous.nurse(3).capacity, homogeneous.capacity_nurse);
+    assert_eq!(homogeneous.nurse(3).shift_end, homogeneous.depot.return_time);
+
+    let data = parsing::parse_json_str(&heterogeneous_fleet_json().to_string()).unwrap();
+    assert_eq!(data.nbr_nurses, 25);
+    assert_eq!(data.nurse(0).start_location, 101);
+    assert!(data.validate().iter().all(|d| !d.is_error()));
+
+    // Nurse 0 starts at 100 from home, the same route on nurse 1 starts at 0 from the depot
+    let route: Vec<u16> = vec![5, 3, 7, 8];
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+    let (home, visits) = evaluator.schedule_route(0, &route);
+    let depot = evaluator.evaluate_route(1, &route);
+    assert!(approx::relative_eq!(home.travel_time, depot.travel_time + 10.0));
+    assert!(visits[0].arrival >= 100.0);
+    assert!(approx::relative_eq!(home.duration, home.return_time - 100.0));
+    assert!(approx::relative_eq!(home.cost, 0.5 * home.duration));
+    assert_eq!(depot.cost, 0.0);
+
+    let routes: Vec<Vec<u16>> = (0..25u16).map(|n| (n * 4 + 1..n * 4 + 5).collect()).collect();
+    let stats = evaluator.evaluate_routes(&routes);
+    let report = checker::check_routes(&routes, &data);
+    for (s, nurse) in stats.iter().zip(report.nurses.iter())
+    {
+        assert!(approx::relative_eq!(s.travel_time, nurse.travel_time));
+        assert!(approx::relative_eq!(s.return_time, nurse.return_time));
+        assert_eq!(s.overload, nurse.overload);
+        assert!(approx::relative_eq!(s.depot_overtime, nurse.depot_overtime));
+    }
+
+    // Capacity and shift per nurse instead of one for the whole fleet
+    let summary = helper::gen_solution_string(&helper::combine_into_chromo(&routes), &data, 0.0);
+    assert!(summary.starts_with("Nurse 00") && !summary.contains("Nurse capacity"));
+    assert!(summary.contains("/50\tShift 100-600\t"));
+
+    // Construction fills the small nurse only up to its capacity
+    for _ in 0..5
+    {
+        let routes = helper::push_forward_insertion(&random_chromo_no_delimit(&mut rng, &data), &data);
+        valid_solution(&routes, &data);
+        assert!(routes[0].iter().map(|&p| data.patient(p).demand).sum::<i32>() <= 50);
+    }
+
+    let chromo = population_init::individual_init::nurse_distributed_chromo(&mut rng, &data);
+    valid_chromosome(&chromo, &data);
+    assert!(helper::split_into_nurses(&chromo)[0].len() < helper::split_into_nurses(&chromo)[1].len());
+
+    let mut json = heterogeneous_fleet_json();
+    json["nurses"][2]["end_location"] = serde_json::json!(12);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "nurses", .. })));
+    let mut json = heterogeneous_fleet_json();
+    json["nbr_nurses"] = serde_json::json!(20);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "nurses", .. })));
+    let mut json = train_0_json();
+    json["travel_times"] = heterogeneous_fleet_json()["travel_times"].clone();
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::MatrixRows { .. })));
+}
+
+/// Nurses 0-4 do wound care and nurses 5-9 injections, patients 1-10 need wound care and 11-15 injections
+fn skills_json() -> serde_json::Value
+{
+    let mut json = heterogeneous_fleet_json();
+    for nurse in 0..10
+    {
+        json["nurses"][nurse]["skills"] = serde_json::json!([if nurse < 5 { "wound_care" } else { "injections" }]);
+    }
+    for patient in 1..=15
+    {
+        json["patients"][patient.to_string()]["skills"] = serde_json::json!([if patient <= 10 { "wound_care" } else { "injections" }]);
+    }
+    json
+}
+
+fn all_qualified(chromo: &Chromosome, data: &parsing::TrainData) -> bool
+{
+    helper::route_ranges(chromo).into_iter().enumerate().all(|(nurse, r)| chromo.genes[r].iter().all(|&gene| data.qualified(nurse, gene)))
+}
+
+#[test]
+pub fn nurse_skills()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json_str(&skills_json().to_string()).unwrap();
+    assert!(data.qualifications().is_restricted());
+    assert!(!parsing::parse_json("train/train_0.json").unwrap().qualifications().is_restricted());
+    assert!(data.qualified(3, 2) && !data.qualified(7, 2) && !data.qualified(20, 2));
+    assert!(data.qualified(20, 16));
+    assert_eq!(data.qualifications().qualified_nurses(12).collect::<Vec<usize>>(), vec![5, 6, 7, 8, 9]);
+    assert!(data.validate().iter().all(|d| !d.is_error()));
+
+    // An unqualified visit breaks a constraint in evaluation, the schedule and the checker alike
+    let mut routes: Vec<Vec<u16>> = vec![Vec::new(); 25];
+    routes[0] = vec![2, 12];
+    routes[6] = vec![11];
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+    let stats = evaluator.evaluate_routes(&routes);
+    assert_eq!(stats[0].unqualified_visits, 1);
+    assert_eq!(stats[6].unqualified_visits, 0);
+    assert!(evaluator::fitness(&stats, &evaluator::PenaltyConfig::default()).1 >= 1.0);
+    assert_eq!(checker::check_routes(&routes, &data).nurses[0].unqualified_visits, vec![12]);
+    let solution = solution::Solution::from_chromosome(&helper::combine_into_chromo(&routes), &data);
+    assert!(!solution.feasible);
+    assert!(solution.routes[0].violations.contains(&solution::Violation::Unqualified { patient: 12 }));
+    assert!(evaluator.route_slack(0, &routes[0]).insertion(13, 0, &data).is_none());
+
+    // Construction and the cross-route operators only give patients to qualified nurses
+    for _ in 0..10
+    {
+        assert!(all_qualified(&random_chromo(&mut rng, &data), &data));
+        assert!(all_qualified(&population_init::individual_init::nurse_distributed_chromo(&mut rng, &data), &data));
+        let routes = helper::push_forward_insertion(&random_chromo_no_delimit(&mut rng, &data), &data);
+        valid_solution(&routes, &data);
+        assert!(all_qualified(&helper::combine_into_chromo(&routes), &data));
+    }
+    let swap = mutation::cross_route::CrossRouteSwapMutation::with_qualifications(1f64, data.qualifications().clone());
+    let insert = mutation::cross_route::CrossRouteInsertMutation::with_qualifications(1f64, data.qualifications().clone());
+    let mut chromo = random_chromo(&mut rng, &data);
+    for _ in 0..200
+    {
+        swap.mutate(&mut rng, &mut chromo);
+        insert.mutate(&mut rng, &mut chromo);
+    }
+    valid_chromosome(&chromo, &data);
+    assert!(all_qualified(&chromo, &data));
+
+    let mut json = skills_json();
+    json["patients"]["16"]["skills"] = serde_json::json!(["sign_language"]);
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert!(data.validate().iter().any(|d| d.issue == validation::Issue::NoQualifiedNurse { patient: 16 }));
+    let mut json = skills_json();
+    json["nurses"][0]["skills"] = serde_json::json!((0..65).map(|i| format!("skill_{}", i)).collect::<Vec<String>>());
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "skills", .. })));
+}
+
+#[test]
+pub fn pluggable_fitness_functions()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let chromos: Vec<Chromosome> = (0..20).map(|_| random_chromo(&mut rng, &data)).collect();
+
+    let penalized = fitness::TrainDataFitness::new(&data);
+    let distance = fitness::DistanceFitness::new(&data);
+    let lexicographic = fitness::LexicographicFitness::new(&data);
+    for chromo in chromos.iter()
+    {
+        let (penalized_fitness, breaks) = penalized.calculate_fitness(chromo);
+        let (travel_time, distance_breaks) = distance.calculate_fitness(chromo);
+        assert_eq!(breaks, distance_breaks);
+        assert!(travel_time <= penalized_fitness);
+        assert_eq!(penalized.calculate_fitness(chromo), calculate_fitness(chromo, &data));
+    }
+
+    let mut ranked: Vec<(f64, f64)> = chromos.iter().map(|c| lexicographic.calculate_fitness(c)).collect();
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert!(ranked.windows(2).all(|w| w[0].1 <= w[1].1));
+
+    // The objective is picked in the config, e.g. with --objective lexicographic
+    let config = config::GaConfig::builder().objective("lexicographic".parse().unwrap()).build().unwrap();
+    let chosen = fitness::ObjectiveFitness::new(&data, config.objective, config.adaptive_penalty);
+    assert_eq!(chosen.calculate_fitness(&chromos[0]), lexicographic.calculate_fitness(&chromos[0]));
+    let chosen = fitness::ObjectiveFitness::new(&data, config::Objective::Distance, false);
+    assert_eq!(chosen.calculate_fitness(&chromos[0]), distance.calculate_fitness(&chromos[0]));
+    assert!("shortest".parse::<config::Objective>().is_err());
+    assert!(matches!(config::GaConfig::builder().objective(config::Objective::Distance).adaptive_penalty(true).build(),
+                     Err(crate::error::ConfigError::OutOfRange { field: "adaptive_penalty", .. })));
+    let toml_path = std::env::temp_dir().join("bio_ai_objective.toml");
+    std::fs::write(&toml_path, "objective = \"distance\"\n").unwrap();
+    assert_eq!(config::GaConfig::load(toml_path.to_str().unwrap()).unwrap().objective, config::Objective::Distance);
+}
+
+#[test]
+pub fn gen_alg_custom_fitness()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    // Objective without routing knowledge: how far each gene is from its own position
+    let displacement = |chromo: &Chromosome| (chromo.iter().enumerate().map(|(i, g)| (i as f64 - *g as f64).abs()).sum::<f64>(), 0.0);
+
+    let population = population_init::pop_init::init_pop_random::<Route, _>(&data, 40, &displacement, &mut rng);
+    let mut algo = gen_alg::GenAlg::new(
+        selection::ElitismSurvivorSelection::new(),
+        selection::TournamentParentSelection::new(2),
+        crossover::OrderOneCrossover::new(),
+        displacement,
+        &data, mutation_vec(&mut rng, &data),
+    );
+    let start = helper::best_fitness(&population).fitness();
+    let mut population = population;
+    for _ in 0..10
+    {
+        population = algo.evolve(&mut rng, &population);
+    }
+    for individual in population.iter()
+    {
+        assert_eq!(individual.fitness(), displacement(individual.chromosome()).0);
+        assert!(individual.feasible());
+    }
+    assert!(helper::best_fitness(&population).fitness() <= start);
+}
+
+#[test]
+pub fn mutations_report_touched_routes()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let mutations: Vec<Box<dyn Mutation>> = vec![
+        Box::new(mutation::in_route::InRouteInversionMutation::new(1f64)),
+        Box::new(mutation::in_route::InRouteScrambleMutation::new(1f64)),
+        Box::new(mutation::in_route::InRouteSwapMutation::new(1f64)),
+        Box::new(mutation::in_route::InRouteInsertMutation::new(1f64)),
+        Box::new(mutation::cross_route::CrossRouteSwapMutation::new(1f64)),
+        Box::new(mutation::cross_route::CrossRouteInsertMutation::new(1f64)),
+    ];
+    for mutation in mutations.iter()
+    {
+        for _ in 0..50
+        {
+            let mut chromo = random_chromo(&mut rng, &data);
+            let before = chromo.clone();
+            let touched = mutation.mutate(&mut rng, &mut chromo);
+            valid_chromosome(&chromo, &data);
+            if let mutation::Touched::Routes(changed) = mutation::Touched::between(&before, &chromo)
+            {
+                assert!(changed.iter().all(|&r| touched.contains(r)), "{:?} not in {:?}", changed, touched);
+            }
+        }
+    }
+    let mut chromo = random_chromo(&mut rng, &data);
+    assert_eq!(mutation::in_route::InRouteSwapMutation::new(0f64).mutate(&mut rng, &mut chromo), mutation::Touched::Unchanged);
+}
+
+#[test]
+pub fn incremental_evaluation()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+    let mutation = mutation::cross_route::CrossRouteInsertMutation::new(1f64);
+
+    let mut chromo = random_chromo(&mut rng, &data);
+    let mut stats = evaluator.evaluate_chromosome(&chromo);
+    for _ in 0..50
+    {
+        let touched = mutation.mutate(&mut rng, &mut chromo);
+        stats = evaluator.reevaluate_chromosome(&chromo, &stats, &touched);
+        assert_eq!(stats, evaluator.evaluate_chromosome(&chromo));
+    }
+
+    let fitness_function = fitness::TrainDataFitness::new(&data);
+    let population = population_init::pop_init::init_pop_random::<Route, _>(&data, 40, &fitness_function, &mut rng);
+    let mut algo = gen_alg::GenAlg::new(
+        selection::ElitismSurvivorSelection::new(),
+        selection::TournamentParentSelection::new(2),
+        crossover::OrderOneCrossover::new(),
+        fitness::TrainDataFitness::new(&data),
+        &data, mutation_vec(&mut rng, &data),
+    );
+    let mut population = population;
+    for _ in 0..5
+    {
+        population = algo.evolve(&mut rng, &population);
+    }
+    for individual in population.iter()
+    {
+        let full = evaluator.evaluate_chromosome(individual.chromosome());
+        assert_eq!(individual.route_stats().unwrap(), &full[..]);
+        assert!(approx::relative_eq!(individual.fitness(), fitness_function.calculate_fitness(individual.chromosome()).0));
+    }
+}
+
+#[test]
+pub fn route_slack_insertion()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+
+    let mut checked = 0;
+    for _ in 0..20
+    {
+        // Short random routes, so most are feasible
+        let chromo = random_chromo(&mut rng, &data);
+        let route: Vec<u16> = chromo.iter().cloned().filter(|&g| g != 0).take(3).collect();
+        if !evaluator.evaluate_route(0, &route).is_feasible()
+        {
+            continue;
+        }
+        let slack = evaluator.route_slack(0, &route);
+        let stats = evaluator.evaluate_route(0, &route);
+        for gene in (1..=data.nbr_patients() as u16).filter(|g| !route.contains(g))
+        {
+            for position in 0..=route.len()
+            {
+                let mut inserted = route.clone();
+                inserted.insert(position, gene);
+                let new_stats = evaluator.evaluate_route(0, &inserted);
+                match slack.insertion(gene, position, &data)
+                {
+                    Some(added) => {
+                        assert!(new_stats.is_feasible());
+                        assert!(approx::relative_eq!(stats.travel_time + added, new_stats.travel_time, max_relative = 1e-9));
+                    }
+                    None => assert!(!new_stats.is_feasible()),
+                }
+                checked += 1;
+            }
+        }
+    }
+    assert!(checked > 0);
+}
+
+/// Individual whose objectives come from a single route with the given stats
+fn with_objectives(travel_time: f64, lateness: f64, overload: i32) -> MultiObjectiveRoute
+{
+    let mut individual = MultiObjectiveRoute::create(Chromosome { genes: vec![1] }, travel_time, overload == 0);
+    individual.set_route_stats(vec![evaluator::RouteStats { travel_time, lateness, overload, return_time: travel_time, ..Default::default() }]);
+    individual
+}
+
+#[test]
+pub fn nsga2_selection()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    // Travel time against lateness, workload follows travel time in a single route
+    let mut population = vec![
+        with_objectives(10.0, 50.0, 0),
+        with_objectives(20.0, 20.0, 0),
+        with_objectives(30.0, 10.0, 0),
+        with_objectives(50.0, 0.0, 0),
+        with_objectives(25.0, 25.0, 0),
+        with_objectives(40.0, 55.0, 0),
+        with_objectives(5.0, 0.0, 3),
+    ];
+    assert!(population[0].objectives().unwrap().dominates(population[5].objectives().unwrap()));
+    assert!(!population[0].objectives().unwrap().dominates(population[1].objectives().unwrap()));
+    // Any overload loses against no overload
+    assert!(population[5].objectives().unwrap().dominates(population[6].objectives().unwrap()));
+
+    let fronts = selection::non_dominated_sort(&population);
+    assert_eq!(fronts[0], vec![0, 1, 2, 3]);
+    assert_eq!(fronts[1], vec![4]);
+    assert_eq!(fronts[2], vec![5]);
+    assert_eq!(fronts[3], vec![6]);
+
+    let distance = selection::crowding_distance(&population, &fronts[0]);
+    assert!(distance[0].is_infinite() && distance[3].is_infinite());
+    assert!(distance[1].is_finite() && distance[2].is_finite());
+
+    // Every tournament of three out of the first six holds a member of the first front, which wins
+    let mut tournament = selection::CrowdedTournamentSelection::new(3);
+    tournament.prepare(&population[..6]);
+    for _ in 0..20
+    {
+        let parents = tournament.select(&mut rng, &population[..6]);
+        assert!(parents.iter().all(|p| p.objectives().unwrap().lateness < 55.0 && p.objectives().unwrap().travel_time != 25.0));
+    }
+    assert!(!selection::NsgaIISurvivorSelection::new().crowding_replacement());
+    assert!(selection::NsgaIISurvivorSelection::new().keeps_parents());
+    assert!(!selection::ElitismSurvivorSelection::new().keeps_parents());
+
+    selection::NsgaIISurvivorSelection::new().select(&mut rng, &mut population, 3);
+    let kept: Vec<f64> = population.iter().map(|i| i.objectives().unwrap().travel_time).collect();
+    assert_eq!(population.len(), 3);
+    assert!(kept.contains(&10.0) && kept.contains(&50.0));
+}
+
+#[test]
+pub fn gen_alg_pareto_front()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let fitness_function = fitness::TrainDataFitness::new(&data);
+    let mut population = population_init::pop_init::init_pop_random::<MultiObjectiveRoute, _>(&data, 40, &fitness_function, &mut rng);
+    let mut algo = gen_alg::GenAlg::new(
+        selection::NsgaIISurvivorSelection::new(),
+        selection::CrowdedTournamentSelection::new(2),
+        crossover::OrderOneCrossover::new(),
+        fitness_function,
+        &data, mutation_vec(&mut rng, &data),
+    );
+    for _ in 0..5
+    {
+        population = algo.evolve(&mut rng, &population);
+    }
+    assert_eq!(population.len(), 40);
+
+    // Parents compete with their offspring. Every child here visits all patients with one nurse,
+    // a non-dominated parent survives a generation of them
+    struct SingleNurse;
+    impl Crossover for SingleNurse {
+        fn crossover(&self, _rng: &mut dyn RngCore, _parent_a: &Chromosome, _parent_b: &Chromosome, t_data: &parsing::TrainData) -> Chromosome {
+            let mut genes: Vec<u16> = (1..=t_data.nbr_patients() as u16).collect();
+            genes.extend(vec![0u16; t_data.nbr_delimiters()]);
+            Chromosome { genes }
+        }
+    }
+    let mut algo = gen_alg::GenAlg::new(
+        selection::NsgaIISurvivorSelection::new(),
+        selection::CrowdedTournamentSelection::new(2),
+        SingleNurse,
+        fitness::TrainDataFitness::new(&data),
+        &data, mutation_vec(&mut rng, &data),
+    ).with_config(GaConfig::builder().xover_prob(1.0).build().unwrap());
+    let next = algo.evolve(&mut rng, &population);
+    for &i in selection::non_dominated_sort(&population)[0].iter()
+    {
+        assert!(next.iter().any(|n| n.chromosome().genes == population[i].chromosome().genes));
+    }
+
+    let fronts = selection::non_dominated_sort(&population);
+    for &i in fronts[0].iter()
+    {
+        let objectives = population[i].objectives().unwrap();
+        assert_eq!(*objectives, evaluator::Objectives::from_routes(&evaluator::RouteEvaluator::new(&data).evaluate_chromosome(population[i].chromosome())));
+        assert!(population.iter().all(|other| !other.objectives().unwrap().dominates(objectives)));
+    }
+
+    let front = solution::ParetoFront {
+        instance_name: data.instance_name.clone(),
+        seed: Some(1),
+        run_time_secs: 0.0,
+        solutions: fronts[0].iter().map(|&i| solution::Solution::from_chromosome(population[i].chromosome(), &data)).collect(),
+    };
+    let plans = parsing::parse_solution_str(&serde_json::to_string(&front).unwrap()).unwrap();
+    assert_eq!(plans.len(), fronts[0].len());
+    assert_eq!(front.solutions[0].objectives, population[fronts[0][0]].objectives().cloned());
+    for plan in plans.iter()
+    {
+        valid_solution(&plan.routes, &data);
+    }
+
+    // Objectives also without incremental evaluation, e.g. with a robustness weight
+    let plain = |chromosome: &Chromosome| fitness::TrainDataFitness::new(&data).calculate_fitness(chromosome);
+    let population = population_init::pop_init::init_pop_random::<MultiObjectiveRoute, _>(&data, 20, &plain, &mut rng);
+    assert!(population.iter().all(|i| i.objectives().is_none()));
+    let mut algo = gen_alg::GenAlg::new(
+        selection::NsgaIISurvivorSelection::new(),
+        selection::CrowdedTournamentSelection::new(2),
+        crossover::OrderOneCrossover::new(),
+        plain,
+        &data, mutation_vec(&mut rng, &data),
+    );
+    assert!(algo.evolve(&mut rng, &population).iter().all(|i| i.objectives().is_some()));
+}
+
+#[test]
+pub fn adaptive_penalty()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_2.json").unwrap();
+    let start = evaluator::PenaltyConfig::default();
+    let mut fitness_function = fitness::TrainDataFitness::adaptive(&data, start, fitness::AdaptivePenalty::new(0.5, 2));
+
+    // A single nurse visiting every patient breaks all constraints
+    let mut genes: Vec<u16> = (1..=data.nbr_patients() as u16).collect();
+    genes.extend(vec![0u16; data.nbr_delimiters()]);
+    let mut chromos: Vec<Chromosome> = vec![Chromosome { genes }];
+    chromos.extend((0..30).map(|_| random_chromo(&mut rng, &data)));
+    let before: Vec<(f64, f64)> = chromos.iter().map(|c| fitness::TrainDataFitness::new(&data).calculate_fitness(c)).collect();
+    assert!(!fitness_function.end_generation());
+    assert!(!fitness_function.end_generation());
+    assert_eq!(*fitness_function.penalty(), start);
+
+    for _ in 0..2
+    {
+        fitness_function.calculate_fitness(&chromos[0]);
+    }
+    assert!(!fitness_function.end_generation());
+    assert!(fitness_function.end_generation());
+
+    let adapted = *fitness_function.penalty();
+    assert!(adapted.lateness > start.lateness);
+    assert!(adapted.overload > start.overload);
+    assert!(adapted.depot_return > start.depot_return);
+    assert!(adapted.break_factor > start.break_factor);
+    // Train_2 has no synchronized visits or breaks, every individual satisfies those
+    assert!(adapted.timing < start.timing);
+    assert!(adapted.working_time < start.working_time);
+    assert_eq!(adapted.missed_visit, start.missed_visit);
+    for (chromo, (fitness, breaks)) in chromos.iter().zip(before.iter())
+    {
+        let (new_fitness, new_breaks) = fitness_function.rescore(chromo);
+        assert_eq!(*breaks, new_breaks);
+        assert!(new_fitness > *fitness);
+    }
+    // Scoring again is not counted, the next update has nothing to go on
+    assert!(!fitness_function.end_generation());
+    assert!(!fitness_function.end_generation());
+    assert_eq!(*fitness_function.penalty(), adapted);
+
+    let fixed = fitness::TrainDataFitness::with_penalty(&data, evaluator::PenaltyConfig { break_factor: 0.0, lateness: 0.0, overload: 0.0, depot_return: 0.0, timing: 0.0, missed_visit: 0.0, working_time: 0.0 });
+    let distance = fitness::DistanceFitness::new(&data);
+    assert_eq!(fixed.calculate_fitness(&chromos[0]), distance.calculate_fitness(&chromos[0]));
+
+    // A migrant scored by another island gets the fitness of the island it arrives at
+    let algo = gen_alg::GenAlg::new(
+        selection::ElitismSurvivorSelection::new(),
+        selection::TournamentParentSelection::new(2),
+        crossover::OrderOneCrossover::new(),
+        fitness_function,
+        &data, mutation_vec(&mut rng, &data),
+    );
+    let migrant = Route::create(chromos[0].clone(), before[0].0, before[0].1 <= 0.0);
+    let arrived: Route = algo.rescore(&migrant);
+    assert!(approx::relative_eq!(arrived.fitness(), evaluator::fitness(&evaluator::RouteEvaluator::new(&data).evaluate_chromosome(&chromos[0]), &adapted).0));
+    assert!(arrived.fitness() > migrant.fitness());
+}
+
+#[test]
+pub fn check_incomplete_solution()
+{
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let mut routes: Vec<Vec<u16>> = vec![(1..51).collect(), (50..101).collect(), vec![0, 999]];
+    routes.extend(vec![Vec::new(); 23]);
+    routes[0].retain(|&p| p != 7);
+
+    let report = checker::check_routes(&routes, &data);
+    assert_eq!(report.extra_routes, 1);
+    assert_eq!(report.missing_patients, vec![7]);
+    assert_eq!(report.duplicate_patients, vec![50]);
+    assert_eq!(report.unknown_patients, vec![0, 999]);
+    assert!(report.nurses[0].overload > 0);
+    assert!(!report.nurses[0].late_visits.is_empty());
+    assert!(!report.is_feasible());
+}
+
 #[test]
 pub fn nurse_distributed_route()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
     let t = population_init::individual_init::nurse_distributed_route(&mut rng, &data);
 
-    valid_chromosome(&t.chromosome());
+    valid_chromosome(t.chromosome(), &data);
 }
 
 #[test]
 pub fn deterministic_crowding()
 {
-    let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let _rng = ChaCha8Rng::from_seed(Default::default());
+    let _data = parsing::parse_json("train/train_0.json").unwrap();
 
     // Tests that it picks best fitness five times
     //or _ in 0..100 {
@@ -85,16 +1021,16 @@ pub fn probabilistic_crowding()
 pub fn hamming()
 {
     let mut rng = ChaCha8Rng::from_seed([43; 32]);
-    let data = parsing::parse_json("train/train_0.json");
-    let parent1 = random_chromo(&mut rng);
-    let parent2 = random_chromo(&mut rng);
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let parent1 = random_chromo(&mut rng, &data);
+    let parent2 = random_chromo(&mut rng, &data);
 
     let crossover = crossover::OrderOneCrossover::new();
 
-    let mut child = crossover
+    let child = crossover
         .crossover(&mut rng, &parent1, &parent2, &data);
 
-    let mut child_2 = crossover
+    let child_2 = crossover
         .crossover(&mut rng, &parent2, &parent1, &data);
 
     println!("{:?}", hamming_distance(&parent1, &child));
@@ -107,10 +1043,10 @@ pub fn hamming()
 #[test]
 pub fn tournament_selection() {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let data = parsing::parse_json("train/train_0.json").unwrap();
     let mut population: Vec<Route> = Vec::new();
-    for i in 0..100 {
-        population.push(Route::new(random_chromo(&mut rng), &data));
+    for _ in 0..100 {
+        population.push(Route::new(random_chromo(&mut rng, &data), &data));
     }
 
     println!("{:?}", &population);
@@ -125,49 +1061,49 @@ pub fn tournament_selection() {
 pub fn order_one_crossover()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut parent_one = random_chromo(&mut rng);
-    let mut parent_two = random_chromo(&mut rng);
+    let parent_one = random_chromo(&mut rng, &data);
+    let parent_two = random_chromo(&mut rng, &data);
 
     assert_ne!(&parent_one.genes, &parent_two.genes);
 
     let crossover = crossover::OrderOneCrossover::new();
 
     let chromie = crossover.crossover(& mut rng, &parent_one, &parent_two, &data );
-    valid_chromosome(&chromie);
+    valid_chromosome(&chromie, &data);
 }
 
 #[test]
 pub fn heuristic_crossover()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
-    let mut parent_one = random_chromo_no_delimit(&mut rng, 100);
-    let mut parent_two = random_chromo_no_delimit(&mut rng, 100);
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let parent_one = random_chromo_no_delimit(&mut rng, &data);
+    let parent_two = random_chromo_no_delimit(&mut rng, &data);
 
     assert_ne!(&parent_one.genes, &parent_two.genes);
 
     let crossover = crossover::HeuristicCrossover::new();
 
     let chromie = crossover.crossover(& mut rng, &parent_one, &parent_two, &data);
-    valid_chromosome_nurseless(&chromie);
+    valid_chromosome_nurseless(&chromie, &data);
 }
 
 #[test]
 pub fn merge_crossover()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
-    let mut parent_one = random_chromo_no_delimit(&mut rng, 100);
-    let mut parent_two = random_chromo_no_delimit(&mut rng, 100);
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let parent_one = random_chromo_no_delimit(&mut rng, &data);
+    let parent_two = random_chromo_no_delimit(&mut rng, &data);
 
     assert_ne!(&parent_one.genes, &parent_two.genes);
 
     let crossover = crossover::MergeCrossover::new();
 
     let chromie = crossover.crossover(& mut rng, &parent_one, &parent_two, &data);
-    valid_chromosome_nurseless(&chromie);
+    valid_chromosome_nurseless(&chromie, &data);
 }
 
 
@@ -175,19 +1111,37 @@ pub fn merge_crossover()
 pub fn valid_random_chromosome()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
     let roote = random_route(&mut rng, &data);
-    valid_chromosome(&roote.chromosome());
+    valid_chromosome(roote.chromosome(), &data);
 
 }
 
+#[test]
+pub fn random_chromosome_any_instance_size()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+
+    for (num_patients, num_nurses) in [(25, 5), (50, 10), (75, 15)]
+    {
+        let small = shrink_instance(&data, num_patients, num_nurses);
+
+        valid_chromosome(&random_chromo(&mut rng, &small), &small);
+        valid_chromosome(&population_init::individual_init::nurse_distributed_chromo(&mut rng, &small), &small);
+        valid_chromosome_nurseless(&random_chromo_no_delimit(&mut rng, &small), &small);
+        assert!(!helper::valid_chromosome(&random_chromo(&mut rng, &small), &data));
+    }
+}
+
 #[test]
 pub fn chromosome_inversion()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::InversionMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -203,8 +1157,9 @@ pub fn chromosome_inversion()
 pub fn in_route_inversion()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::in_route::InRouteInversionMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -221,8 +1176,9 @@ pub fn in_route_inversion()
 pub fn in_route_swap()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::in_route::InRouteSwapMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -240,8 +1196,9 @@ pub fn in_route_swap()
 pub fn in_route_insert()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::in_route::InRouteInsertMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -259,8 +1216,9 @@ pub fn in_route_insert()
 pub fn cross_route_insert()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::cross_route::CrossRouteInsertMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -278,8 +1236,9 @@ pub fn cross_route_insert()
 pub fn cross_route_swap()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::cross_route::CrossRouteSwapMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -298,15 +1257,20 @@ pub fn cross_route_swap()
 pub fn split_into_nurses()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let chromo = random_chromo(&mut rng, &data);
 
-    let mutation = mutation::in_route::InRouteInversionMutation::new(1f64);
+    let _mutation = mutation::in_route::InRouteInversionMutation::new(1f64);
 
     let mongo = helper::split_into_nurses(&chromo);
     println!("{:?}", mongo);
     println!("{:?}", chromo);
 
+    // Without delimiters the chromosome is one route, as for route_ranges
+    let undelimited = random_chromo_no_delimit(&mut rng, &data);
+    assert_eq!(helper::split_into_nurses(&undelimited), vec![undelimited.genes.clone()]);
+    assert_eq!(helper::route_ranges(&undelimited), vec![0..undelimited.len()]);
 }
 
 #[test]
@@ -314,14 +1278,15 @@ pub fn push_forward_insertion()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
 
-    let mut chromo = random_chromo_no_delimit(&mut rng, 100);
-    let data = parsing::parse_json("train/train_0.json");
+    let data = parsing::parse_json("train/train_0.json").unwrap();
+    let chromo = random_chromo_no_delimit(&mut rng, &data);
 
     let test_t = helper::push_forward_insertion(&chromo, &data);
 
     //let mongo = helper::split_into_nurses(&chromo);
 
-    valid_chromosome_nurseless(&test_t.into_iter().flatten().into_iter().collect());
+    valid_solution(&test_t, &data);
+    valid_chromosome_nurseless(&test_t.into_iter().flatten().collect(), &data);
 
 }
 
@@ -329,9 +1294,9 @@ pub fn push_forward_insertion()
 pub fn fitness_nurseless()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo_no_delimit(&mut rng, 100);
+    let chromo = random_chromo_no_delimit(&mut rng, &data);
 
     let mut chromo_no_nurse = chromo.clone();
     chromo_no_nurse.genes.retain(|&x| x != 0);
@@ -350,8 +1315,9 @@ pub fn combine_into_chromo()
 {
 
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let chromo = random_chromo(&mut rng, &data);
 
     let mongo = helper::split_into_nurses(&chromo);
 
@@ -366,8 +1332,9 @@ pub fn combine_into_chromo()
 pub fn chromosome_scramble()
 {
     let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let data = parsing::parse_json("train/train_0.json").unwrap();
 
-    let mut chromo = random_chromo(&mut rng);
+    let mut chromo = random_chromo(&mut rng, &data);
 
     let mutation = mutation::ScrambleMutation::new(1f64);
     let chromo_copy = chromo.clone();
@@ -383,11 +1350,559 @@ pub fn chromosome_scramble()
 pub fn kmeans()
 {
 
-    let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let data = parsing::parse_json("train/train_0.json");
+    let _rng = ChaCha8Rng::from_seed(Default::default());
+    let _data = parsing::parse_json("train/train_0.json").unwrap();
     //population_init::init_pop_kmeans::<Route>(&data, 100, &mut rng);
     todo!()
 }
 
+#[test]
+pub fn continuity_of_care()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let mut data = parsing::parse_json("train/train_0.json").unwrap();
+    let chromo = random_chromo(&mut rng, &data);
+    let routes = helper::split_into_nurses(&chromo);
+    let penalty = evaluator::PenaltyConfig::default();
+    let before = evaluator::fitness(&evaluator::RouteEvaluator::new(&data).evaluate_chromosome(&chromo), &penalty).0;
+    assert!(data.continuity().is_none());
+
+    // Yesterday's solution through its json, the same routes today cost nothing extra
+    let json = serde_json::to_string(&solution::Solution::from_chromosome(&chromo, &data)).unwrap();
+    let previous = parsing::parse_solution_str(&json).unwrap();
+    data.set_previous_routes(&previous[0].routes, 5.0);
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+    let stats = evaluator.evaluate_chromosome(&chromo);
+    assert!(stats.iter().all(|s| s.nurse_changes == 0 && s.continuity_cost == 0.0));
+    assert_eq!(evaluator::fitness(&stats, &penalty).0, before);
+
+    // Swapping two patients between nurses changes the nurse of both
+    let mutation = mutation::cross_route::CrossRouteSwapMutation::new(1f64);
+    let mut child = chromo.clone();
+    let touched = mutation.mutate(&mut rng, &mut child);
+    let stats = evaluator.reevaluate_chromosome(&child, &stats, &touched);
+    assert_eq!(stats, evaluator.evaluate_chromosome(&child));
+    assert_eq!(stats.iter().map(|s| s.nurse_changes).sum::<u32>(), 2);
+    assert!(approx::relative_eq!(stats.iter().map(|s| s.continuity_cost).sum::<f64>(), 10.0));
+
+    // Reported per patient
+    let solution = solution::Solution::from_chromosome(&child, &data);
+    let moved: Vec<&solution::Visit> = solution.routes.iter().flat_map(|r| r.visits.iter()).filter(|v| v.continuity_cost > 0.0).collect();
+    assert_eq!(moved.len(), 2);
+    for route in solution.routes.iter()
+    {
+        for visit in route.visits.iter()
+        {
+            assert_eq!(visit.previous_nurse, Some(routes.iter().position(|r| r.contains(&visit.patient)).unwrap()));
+            assert_eq!(visit.continuity_cost > 0.0, visit.previous_nurse != Some(route.nurse));
+        }
+    }
+    assert!(helper::gen_solution_string(&child, &data, solution.fitness.unwrap()).contains("Continuity cost: 10.00 (2 patients with another nurse)"));
+}
+
+/// Five days, patients 1-10 on Mon/Wed/Fri or Tue/Wed/Thu, 11-20 on any two days and the rest on any one day
+fn week_json() -> serde_json::Value
+{
+    let mut json = train_0_json();
+    json["nbr_days"] = serde_json::json!(5);
+    for patient in 1..=10
+    {
+        json["patients"][patient.to_string()]["patterns"] = serde_json::json!([[0, 2, 4], [1, 2, 3]]);
+    }
+    for patient in 11..=20
+    {
+        json["patients"][patient.to_string()]["frequency"] = serde_json::json!(2);
+    }
+    json
+}
+
+#[test]
+pub fn periodic_planning()
+{
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let single = parsing::parse_json("train/train_0.json").unwrap();
+    assert_eq!(single.planning_days(), 1);
+    assert_eq!(single.visit_patterns(1), &[1]);
+
+    let data = parsing::parse_json_str(&week_json().to_string()).unwrap();
+    assert_eq!(data.planning_days(), 5);
+    assert_eq!(data.visit_patterns(1), &[0b10101, 0b01110]);
+    assert_eq!(data.visit_patterns(11).len(), 10);
+    assert_eq!(data.visit_patterns(50).len(), 5);
+
+    let plan = periodic::WeekPlan::random(&mut rng, &data);
+    assert_eq!(plan.days.len(), 5);
+    assert_eq!(plan.pattern_violations(&data), 0);
+    assert!(plan.days.iter().all(|day| day.iter().filter(|&&g| g == 0).count() == data.nbr_delimiters()));
+    let visits: usize = plan.days.iter().map(|day| day.iter().filter(|&&g| g != 0).count()).sum();
+    assert_eq!(visits, 10 * 3 + 10 * 2 + 80);
+
+    // Every day is evaluated like a single-day instance
+    let penalty = evaluator::PenaltyConfig::default();
+    let stats = plan.evaluate(&data);
+    let daily: f64 = plan.days.iter().map(|day| calculate_fitness(day, &data).0).sum();
+    assert!(approx::relative_eq!(plan.fitness(&stats, &data, &penalty).0, daily, max_relative = 1e-9));
+
+    let mut changed = plan.clone();
+    for _ in 0..100
+    {
+        changed.change_pattern(&mut rng, &data);
+    }
+    assert_eq!(changed.pattern_violations(&data), 0);
+
+    // Without a qualified nurse a patient keeps its nurse, only an optional one goes to the pool
+    let nurses_of = |plan: &periodic::WeekPlan, gene: u16| -> Vec<usize> {
+        let mut nurses: Vec<usize> = plan.days.iter()
+            .filter_map(|day| helper::split_into_nurses(day).iter().position(|r| r.contains(&gene)))
+            .collect();
+        nurses.sort();
+        nurses.dedup();
+        nurses
+    };
+    let mut json = week_json();
+    json["patients"]["1"]["skills"] = serde_json::json!(["dialysis"]);
+    let unqualified = parsing::parse_json_str(&json.to_string()).unwrap();
+    json["patients"]["1"]["drop_cost"] = serde_json::json!(50.0);
+    let optional = parsing::parse_json_str(&json.to_string()).unwrap();
+    for week in [&unqualified, &optional]
+    {
+        assert_eq!(week.qualifications().qualified_nurses(1).count(), 0);
+        let mut plan = periodic::WeekPlan::random(&mut rng, week);
+        let before = nurses_of(&plan, 1);
+        for _ in 0..200
+        {
+            plan.change_pattern(&mut rng, week);
+        }
+        let after = nurses_of(&plan, 1);
+        assert!(after.iter().all(|n| before.contains(n) || Some(*n) == week.pool_route()), "{:?} after {:?}", after, before);
+    }
+    changed.days[0].genes.push(1);
+    assert!(changed.pattern_violations(&data) > 0);
+
+    let mutations = mutation_vec(&mut rng, &data);
+    let improved = periodic::improve(&mut rng, plan.clone(), &data, &mutations, &penalty, 2000);
+    assert_eq!(improved.pattern_violations(&data), 0);
+    assert!(improved.fitness(&improved.evaluate(&data), &data, &penalty) <= plan.fitness(&stats, &data, &penalty));
+
+    let week = improved.week_solution(&data);
+    assert_eq!(week.days.len(), 5);
+    assert_eq!(week.patient_days[0].len(), 3);
+    assert_eq!(week.patient_days[10].len(), 2);
+    assert!(approx::relative_eq!(week.objective, improved.evaluate(&data).iter().flatten().map(|s| s.travel_time).sum::<f64>(), max_relative = 1e-9));
+
+    // Operators leave days with too few patients alone
+    let mut sparse: Chromosome = vec![0u16; data.nbr_delimiters()].into_iter().collect();
+    assert_eq!(mutation::in_route::InRouteSwapMutation::new(1f64).mutate(&mut rng, &mut sparse), mutation::Touched::Unchanged);
+    sparse.genes.insert(3, 7);
+    assert_eq!(mutation::cross_route::CrossRouteSwapMutation::new(1f64).mutate(&mut rng, &mut sparse), mutation::Touched::Unchanged);
+
+    let mut json = week_json();
+    json["patients"]["1"]["patterns"] = serde_json::json!([[0, 2, 5]]);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "patterns", .. })));
+    let mut json = week_json();
+    json["patients"]["12"]["frequency"] = serde_json::json!(6);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "frequency", .. })));
+    let mut json = week_json();
+    json["nbr_days"] = serde_json::json!(15);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "nbr_days", .. })));
+}
+
+#[test]
+pub fn synchronized_and_precedence_visits()
+{
+    let mut json = train_0_json();
+    json["synchronized"] = serde_json::json!([[3, 17]]);
+    json["precedences"] = serde_json::json!([{"first": 5, "then": 9, "min_gap": 60.0, "max_gap": 200.0}]);
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert!(data.has_dependencies());
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+
+    // The earlier of the two synchronized visits waits, the second visit of the precedence pair waits for the gap
+    let mut routes: Vec<Vec<u16>> = vec![Vec::new(); 25];
+    routes[0] = vec![3, 5];
+    routes[1] = vec![17];
+    routes[2] = vec![9];
+    let alone: Vec<f64> = [(0, 3), (1, 17)].iter().map(|&(n, p)| evaluator.schedule_route(n, &routes[n]).1.iter().find(|v| v.patient == p).unwrap().start).collect();
+    let schedules = evaluator.schedule_routes(&routes);
+    let start = |patient: u16| schedules.iter().flat_map(|s| s.1.iter()).find(|v| v.patient == patient).unwrap().start;
+    assert!(approx::relative_eq!(start(3), start(17)));
+    assert!(approx::relative_eq!(start(3), alone[0].max(alone[1])));
+    assert!(start(9) - start(5) >= 60.0 - 1e-9);
+    assert!(evaluator.timing_violations(&schedules).is_empty());
+    assert!(schedules.iter().all(|s| s.0.timing_violations == 0));
+
+    let stats = evaluator.evaluate_routes(&routes);
+    let report = checker::check_routes(&routes, &data);
+    assert!(report.unmet_pairs.is_empty());
+    for (s, nurse) in stats.iter().zip(report.nurses.iter())
+    {
+        assert!(approx::relative_eq!(s.return_time, nurse.return_time));
+    }
 
+    // One nurse can not do both synchronized visits
+    routes[0] = vec![3, 17];
+    routes[1] = vec![5];
+    let chromo = helper::combine_into_chromo(&routes);
+    let stats = evaluator.evaluate_chromosome(&chromo);
+    assert_eq!(stats[0].timing_violations, 1);
+    assert!(stats[0].timing_deviation > 0.0);
+    assert_eq!(checker::check_routes(&routes, &data).unmet_pairs.len(), 1);
+    let solution = solution::Solution::from_chromosome(&chromo, &data);
+    assert!(!solution.feasible);
+    assert!(solution.routes[0].violations.iter().any(|v| matches!(v, solution::Violation::Synchronization { patients: [3, 17], .. })));
+    assert!(helper::gen_solution_string(&chromo, &data, solution.fitness.unwrap()).contains("Synchronized or precedence pairs not met: 1"));
+
+    // Routes depend on each other, so a mutation re-evaluates all of them
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let mut child = chromo.clone();
+    let touched = mutation::cross_route::CrossRouteInsertMutation::new(1f64).mutate(&mut rng, &mut child);
+    assert_eq!(evaluator.reevaluate_chromosome(&child, &stats, &touched), evaluator.evaluate_chromosome(&child));
+
+    let mut json = train_0_json();
+    json["synchronized"] = serde_json::json!([[3, 3]]);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "synchronized", .. })));
+    let mut json = train_0_json();
+    json["precedences"] = serde_json::json!([{"first": 5, "then": 9, "min_gap": 60.0, "max_gap": 30.0}]);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "precedences", .. })));
+}
 
+#[test]
+pub fn optional_patients()
+{
+    let mut json = train_0_json();
+    json["patients"]["3"]["drop_cost"] = serde_json::json!(50.0);
+    json["patients"]["7"]["drop_cost"] = serde_json::json!(20.0);
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert_eq!(data.pool_route(), Some(25));
+    assert_eq!(data.nbr_delimiters(), 25);
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+
+    // Patients 3 and 7 in the pool cost their drop cost, patient 9 has to be visited
+    let mut routes: Vec<Vec<u16>> = vec![Vec::new(); 26];
+    routes[0] = (1..=100).filter(|&p| p != 3 && p != 7).collect();
+    routes[25] = vec![3, 7];
+    let chromo = helper::combine_into_chromo(&routes);
+    assert!(helper::valid_chromosome(&chromo, &data));
+    let stats = evaluator.evaluate_chromosome(&chromo);
+    assert_eq!(stats.len(), 26);
+    assert!(stats[25].pool);
+    assert_eq!((stats[25].dropped, stats[25].missed_visits), (2, 0));
+    assert!(approx::relative_eq!(stats[25].drop_cost, 70.0));
+    let (fitness, _) = fitness::DistanceFitness::new(&data).calculate_fitness(&chromo);
+    assert!(approx::relative_eq!(fitness, stats[0].travel_time + 70.0));
+
+    let solution = solution::Solution::from_chromosome(&chromo, &data);
+    assert_eq!(solution.routes.len(), 25);
+    assert_eq!(solution.unassigned, vec![3, 7]);
+    assert!(approx::relative_eq!(solution.drop_cost, 70.0));
+    // Dropping patients trades travel time against drop cost in multi-objective mode
+    let objectives = solution.objectives.unwrap();
+    assert!(approx::relative_eq!(objectives.travel_time, solution.objective));
+    assert!(approx::relative_eq!(objectives.drop_cost, 70.0));
+    assert!(helper::gen_solution_string(&chromo, &data, solution.fitness.unwrap()).contains("Unassigned patients: [3, 7] (drop cost 70.00)"));
+    let report = checker::check_routes(&helper::split_unassigned(&chromo, &data).0, &data);
+    assert_eq!(report.dropped_patients, vec![3, 7]);
+    assert!(report.missing_patients.is_empty());
+
+    routes[0].retain(|&p| p != 9);
+    routes[25].push(9);
+    let chromo = helper::combine_into_chromo(&routes);
+    assert_eq!(evaluator.evaluate_chromosome(&chromo)[25].missed_visits, 1);
+    assert!(!solution::Solution::from_chromosome(&chromo, &data).feasible);
+    assert_eq!(checker::check_routes(&helper::split_unassigned(&chromo, &data).0, &data).missing_patients, vec![9]);
+
+    // Only optional patients go to the pool, any pool patient can come back
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let optional: Vec<bool> = (0..=100).map(|gene| gene == 3 || gene == 7).collect();
+    let mut child = helper::combine_into_chromo(&[(1..=100).collect(), Vec::new()]);
+    mutation::pool::PoolRemoveMutation::new(1f64, optional).mutate(&mut rng, &mut child);
+    let pool = helper::split_into_nurses(&child)[1].clone();
+    assert!(pool == vec![3] || pool == vec![7]);
+    let touched = mutation::pool::PoolInsertMutation::new(1f64).mutate(&mut rng, &mut child);
+    assert_eq!(touched, mutation::Touched::Routes(vec![1, 0]));
+    assert!(helper::split_into_nurses(&child)[1].is_empty());
+
+    // Cross-route operators leave the pool alone, inserts can append to a route
+    let mut child = helper::combine_into_chromo(&[vec![1, 2], vec![4], vec![3, 7]]);
+    let insert = mutation::cross_route::CrossRouteInsertMutation::new(1f64).excluding_pool();
+    let swap = mutation::cross_route::CrossRouteSwapMutation::new(1f64).excluding_pool();
+    let mut appended = false;
+    for _ in 0..50 {
+        let before = helper::split_into_nurses(&child);
+        insert.mutate(&mut rng, &mut child);
+        let inserted = helper::split_into_nurses(&child);
+        appended |= (0..2).any(|r| inserted[r].len() > before[r].len() && !before[r].is_empty() && !before[r].contains(inserted[r].last().unwrap()));
+        swap.mutate(&mut rng, &mut child);
+        assert_eq!(helper::split_into_nurses(&child)[2], vec![3, 7]);
+    }
+    assert!(appended);
+
+    let mut json = train_0_json();
+    json["patients"]["3"]["drop_cost"] = serde_json::json!(-1.0);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::NegativeValue { field: "drop_cost", .. })));
+}
+
+#[test]
+pub fn nurse_breaks()
+{
+    let mut json = train_0_json();
+    json["break_rule"] = serde_json::json!({"duration": 30.0, "start": 300.0, "end": 500.0});
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    let plain = parsing::parse_json_str(&train_0_json().to_string()).unwrap();
+    let evaluator = evaluator::RouteEvaluator::new(&data);
+
+    // The nurse waits from patient 3 until patient 4's window opens, the break fits in there
+    let mut routes: Vec<Vec<u16>> = vec![Vec::new(); 25];
+    routes[0] = vec![5, 3, 4, 2, 1];
+    let stats = evaluator.evaluate_route(0, &routes[0]);
+    let (after, start) = stats.break_at.unwrap();
+    assert_eq!(after, 2);
+    assert!(start >= 300.0 && start + 30.0 <= 500.0);
+    assert!(approx::relative_eq!(stats.return_time, evaluator::RouteEvaluator::new(&plain).evaluate_route(0, &routes[0]).return_time));
+    assert_eq!(stats.violations(), 0);
+    assert!(evaluator.evaluate_route(1, &[]).break_at.is_none());
+    let report = checker::check_routes(&routes, &data);
+    assert!(report.nurses[0].is_feasible());
+    assert!(approx::relative_eq!(report.nurses[0].break_start.unwrap(), start));
+
+    let chromo = helper::combine_into_chromo(&routes);
+    let solution = solution::Solution::from_chromosome(&chromo, &data);
+    assert_eq!(solution.routes[0].rest_break, Some(solution::RestBreak { after_visits: 2, start, end: start + 30.0 }));
+    assert!(helper::gen_solution_string(&chromo, &data, solution.fitness.unwrap()).contains(&format!("->Break({:.2}-{:.2})->4", start, start + 30.0)));
+
+    // Working at most 300 minutes without a break: fine until the break, far too long after it
+    json["max_working_time"] = serde_json::json!(300.0);
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    let stats = evaluator::RouteEvaluator::new(&data).evaluate_route(0, &routes[0]);
+    assert!(stats.overwork > 0.0);
+    assert_eq!(stats.violations(), 1);
+    let report = checker::check_routes(&routes, &data);
+    assert!(approx::relative_eq!(report.nurses[0].overwork, stats.overwork));
+    assert!(!solution::Solution::from_chromosome(&chromo, &data).feasible);
+
+    // No position avoids breaking a constraint when the window closes before patient 5 is done
+    let mut json = train_0_json();
+    json["break_rule"] = serde_json::json!({"duration": 30.0, "start": 50.0, "end": 90.0});
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert_eq!(evaluator::RouteEvaluator::new(&data).evaluate_route(0, &routes[0]).violations(), 1);
+    assert!(!checker::check_routes(&routes, &data).nurses[0].is_feasible());
+
+    json["break_rule"] = serde_json::json!({"duration": 60.0, "start": 50.0, "end": 90.0});
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "break_rule", .. })));
+}
+
+#[test]
+pub fn time_dependent_travel()
+{
+    let mut json = train_0_json();
+    json["speed_profile"] = serde_json::json!([{"start": 0.0, "speed": 0.5}, {"start": 100.0, "speed": 1.0}]);
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    let plain = parsing::parse_json_str(&train_0_json().to_string()).unwrap();
+    assert!(data.is_time_dependent());
+
+    // Half speed until 100, then the matrix speed. Leaving later never arrives earlier
+    let distance = data.travel_times[0][5];
+    assert!(approx::relative_eq!(data.travel_time(0, 5, 0.0), 2.0 * distance, max_relative = 1e-9));
+    assert!(approx::relative_eq!(data.travel_time(0, 5, 100.0), distance, max_relative = 1e-9));
+    assert!(approx::relative_eq!(data.travel_time(0, 5, 100.0 - distance / 4.0), distance / 4.0 + distance * 7.0 / 8.0, max_relative = 1e-9));
+    let arrivals: Vec<f64> = (0..200).map(|depart| depart as f64 + data.travel_time(0, 5, depart as f64)).collect();
+    assert!(arrivals.windows(2).all(|a| a[0] <= a[1]));
+    assert!(approx::relative_eq!(plain.travel_time(0, 5, 0.0), distance, max_relative = 1e-9));
+
+    // Rush hour makes the route slower, and the checker agrees with the evaluator
+    let mut routes: Vec<Vec<u16>> = vec![Vec::new(); 25];
+    routes[0] = vec![5, 3, 4, 2, 1];
+    let stats = evaluator::RouteEvaluator::new(&data).evaluate_route(0, &routes[0]);
+    assert!(stats.travel_time > evaluator::RouteEvaluator::new(&plain).evaluate_route(0, &routes[0]).travel_time);
+    let report = checker::check_routes(&routes, &data);
+    assert!(approx::relative_eq!(report.nurses[0].travel_time, stats.travel_time));
+    assert!(approx::relative_eq!(report.nurses[0].return_time, stats.return_time));
+
+    // Insertions are checked with the slow morning trips, so only the last nurse can end up with late visits
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let routes = helper::push_forward_insertion(&random_chromo_no_delimit(&mut rng, &data), &data);
+    let report = checker::check_routes(&routes, &data);
+    assert!(report.nurses[..24].iter().all(|n| n.late_visits.is_empty() && n.depot_overtime <= 0.0));
+
+    json["speed_profile"] = serde_json::json!([{"start": 100.0, "speed": 0.5}, {"start": 100.0, "speed": 1.0}]);
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "speed_profile", .. })));
+}
+
+#[test]
+pub fn distance_metrics()
+{
+    // Without a matrix the travel times come from the coordinates
+    let mut json = train_0_json();
+    let given = parsing::parse_json_str(&json.to_string()).unwrap();
+    json.as_object_mut().unwrap().remove("travel_times");
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert_eq!(data.travel_times.len(), given.travel_times.len());
+    for (row, given_row) in data.travel_times.iter().zip(&given.travel_times) {
+        for (&t, &g) in row.iter().zip(given_row) {
+            assert!(approx::relative_eq!(t, g, epsilon = 1e-6, max_relative = 1e-6));
+        }
+    }
+    assert_eq!(data.coordinates(0), Some((data.depot.x_coord, data.depot.y_coord)));
+    assert_eq!(data.coordinates(data.nbr_patients() + 1), None);
+
+    let (a, b) = (data.coordinates(1).unwrap(), data.coordinates(2).unwrap());
+    json["distance_metric"] = serde_json::json!("manhattan");
+    let manhattan = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert!(approx::relative_eq!(manhattan.travel_times[1][2], (a.0 - b.0).abs() + (a.1 - b.1).abs()));
+    json["distance_metric"] = serde_json::json!("rounded_euclidean");
+    let rounded = parsing::parse_json_str(&json.to_string()).unwrap();
+    assert!(approx::relative_eq!(rounded.travel_times[1][2], (data.travel_times[1][2] * 10.0).floor() / 10.0));
+
+    // Oslo to Bergen as the crow flies
+    let km = parsing::DistanceMetric::Haversine.distance((10.75, 59.91), (5.32, 60.39));
+    assert!(approx::relative_eq!(km, 305.135, epsilon = 0.01));
+
+    json["distance_metric"] = serde_json::json!("chebyshev");
+    assert!(parsing::parse_json_str(&json.to_string()).is_err());
+}
+
+#[test]
+pub fn robustness()
+{
+    let mut json = train_0_json();
+    json["uncertainty"] = serde_json::json!({"samples": 1});
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    let uncertainty = data.uncertainty.unwrap();
+    assert_eq!(uncertainty.travel, parsing::DurationNoise::Fixed);
+
+    // Without noise every sample is the deterministic schedule
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let routes = helper::push_forward_insertion(&random_chromo_no_delimit(&mut rng, &data), &data);
+    let stats = evaluator::RouteEvaluator::new(&data).evaluate_routes(&routes);
+    let report = robustness::evaluate(&routes, &data, &uncertainty);
+    assert!(approx::relative_eq!(report.expected_late_visits(), stats.iter().map(|s| s.late_visits as f64).sum::<f64>()));
+    for (minutes, s) in report.expected_overtime.iter().zip(stats.iter()) {
+        assert!(approx::relative_eq!(*minutes, s.depot_overtime, epsilon = 1e-9));
+    }
+
+    // Noisy durations keep their nominal mean, and a seed gives the same samples
+    let noise = parsing::DurationNoise::LogNormal { sigma: 0.3 };
+    let mean = (0..20000).map(|_| noise.sample(&mut rng, 10.0)).sum::<f64>() / 20000.0;
+    assert!(approx::relative_eq!(mean, 10.0, max_relative = 0.02));
+    json["uncertainty"] = serde_json::json!({"travel": {"distribution": "log_normal", "sigma": 0.3},
+        "care": {"distribution": "uniform", "spread": 0.2}, "samples": 50, "seed": 7, "fitness_weight": 1.0});
+    let data = parsing::parse_json_str(&json.to_string()).unwrap();
+    let uncertainty = data.uncertainty.unwrap();
+    let report = robustness::evaluate(&routes, &data, &uncertainty);
+    assert_eq!(report.late_probability, robustness::evaluate(&routes, &data, &uncertainty).late_probability);
+    assert!(report.late_probability.iter().all(|p| (0.0..=1.0).contains(p)));
+    assert!(report.expected_late_visits() > 0.0);
+
+    // As a fitness term it adds the weighted expectations and turns incremental evaluation off
+    let chromosome = Chromosome { genes: routes.join(&0) };
+    let inner = fitness::TrainDataFitness::new(&data);
+    let robust = fitness::RobustFitness::new(fitness::TrainDataFitness::new(&data), &data);
+    let (plain, _) = inner.calculate_fitness(&chromosome);
+    let (fitness, _) = robust.calculate_fitness(&chromosome);
+    assert!(approx::relative_eq!(fitness - plain, report.expected_late_visits() + report.total_expected_overtime(), max_relative = 1e-9));
+    assert!(robust.fitness_from_routes(&stats).is_none());
+    // Chromosomes without delimiters are split by push forward insertion first
+    let undelimited = random_chromo_no_delimit(&mut ChaCha8Rng::from_seed(Default::default()), &data);
+    let split = helper::push_forward_insertion(&undelimited, &data);
+    let report = robustness::evaluate(&split, &data, &uncertainty);
+    let (fitness, _) = robust.calculate_fitness(&undelimited);
+    assert!(approx::relative_eq!(fitness - inner.calculate_fitness(&undelimited).0, report.expected_late_visits() + report.total_expected_overtime(), max_relative = 1e-9));
+
+    json["uncertainty"] = serde_json::json!({"care": {"distribution": "uniform", "spread": 1.5}});
+    assert!(matches!(parsing::parse_json_str(&json.to_string()), Err(ParseError::InvalidField { field: "uncertainty", .. })));
+}
+
+#[test]
+pub fn reoptimize()
+{
+    let data = parsing::parse_json_str(&train_0_json().to_string()).unwrap();
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let mut routes = helper::push_forward_insertion(&random_chromo_no_delimit(&mut rng, &data), &data);
+    // Patient `added` calls in during the day
+    let added = routes[3].pop().unwrap();
+    let plan = solution::Solution::from_chromosome(&Chromosome { genes: routes.join(&0) }, &data);
+
+    let mut disruption = reoptimize::Disruption::at(200.0, &plan);
+    assert!(disruption.fixed.iter().zip(plan.routes.iter()).all(|(&fixed, r)| r.visits[..fixed].iter().all(|v| v.start <= 200.0)));
+    let cancelled = plan.routes[0].visits[disruption.fixed[0]].patient;
+    disruption.added = vec![added];
+    disruption.cancelled = vec![cancelled];
+
+    // The sub-instance holds the unfixed patients, nurses start when and where their fixed visits end
+    let sub = reoptimize::SubInstance::new(&data, &plan, &disruption).unwrap();
+    let nbr_fixed: usize = disruption.fixed.iter().sum();
+    assert_eq!(sub.data.nbr_patients(), data.nbr_patients() - nbr_fixed - 1);
+    for (nurse, &fixed) in disruption.fixed.iter().enumerate().filter(|(_, &f)| f > 0) {
+        assert!(sub.data.nurse(nurse).shift_start >= 200.0);
+        assert_eq!(sub.data.travel_times[sub.data.nurse(nurse).start_location][0], data.travel_times[plan.routes[nurse].visits[fixed - 1].patient as usize][0]);
+    }
+
+    // The seed is the previous plan: unchanged travel for nurses without changes, fixed visits kept as planned
+    let seed = sub.merge(&sub.seed_chromosome(), &data);
+    assert!(approx::relative_eq!(seed.routes[1].travel_time, plan.routes[1].travel_time, max_relative = 1e-9));
+    let new_plan = reoptimize::reoptimize(&mut rng, &data, &plan, &disruption, &config::GaConfig::builder().reoptimize_generations(20).build().unwrap()).unwrap();
+    for solution in [&seed, &new_plan] {
+        let mut visited: Vec<u16> = solution.routes.iter().flat_map(|r| r.visits.iter().map(|v| v.patient)).collect();
+        visited.sort_unstable();
+        assert_eq!(visited, (1..=data.nbr_patients() as u16).filter(|&p| p != cancelled).collect::<Vec<u16>>());
+        for (route, (previous, &fixed)) in solution.routes.iter().zip(plan.routes.iter().zip(disruption.fixed.iter())) {
+            assert!(route.visits[..fixed].iter().zip(previous.visits.iter()).all(|(a, b)| a.patient == b.patient && a.start == b.start));
+            assert!(route.visits[fixed..].iter().all(|v| v.arrival >= 200.0));
+        }
+    }
+
+    let nurse = disruption.fixed.iter().position(|&f| f > 0).unwrap();
+    disruption.cancelled = vec![plan.routes[nurse].visits[0].patient];
+    assert!(matches!(reoptimize::SubInstance::new(&data, &plan, &disruption), Err(ParseError::InvalidField { field: "cancelled", .. })));
+}
+
+#[test]
+pub fn ga_config()
+{
+    use crate::config::GaConfig;
+    use crate::error::ConfigError;
+
+    let defaults = GaConfig::builder().build().unwrap();
+    assert_eq!(defaults, GaConfig::default());
+    assert_eq!(defaults.generations, 100);
+    assert!(!defaults.adaptive_penalty);
+    assert!(approx::relative_eq!(defaults.mutation_probability(10.0), 0.07));
+    assert!(approx::relative_eq!(defaults.mutation_probability(0.0), 0.47));
+
+    // Fields left out keep their defaults, in either format
+    let toml_path = std::env::temp_dir().join("bio_ai_ga_config.toml");
+    std::fs::write(&toml_path, "pop_size = 50\nxover_prob = 0.8\n").unwrap();
+    let config = GaConfig::load(toml_path.to_str().unwrap()).unwrap();
+    assert_eq!(config, GaConfig::builder().pop_size(50).xover_prob(0.8).build().unwrap());
+    let json_path = std::env::temp_dir().join("bio_ai_ga_config.json");
+    std::fs::write(&json_path, serde_json::to_string(&config).unwrap()).unwrap();
+    assert_eq!(GaConfig::load(json_path.to_str().unwrap()).unwrap(), config);
+
+    std::fs::write(&toml_path, "pop_sise = 50\n").unwrap();
+    assert!(matches!(GaConfig::load(toml_path.to_str().unwrap()), Err(ConfigError::Toml(_))));
+    std::fs::write(&toml_path, "islands = 0\n").unwrap();
+    assert!(matches!(GaConfig::load(toml_path.to_str().unwrap()), Err(ConfigError::OutOfRange { field: "islands", .. })));
+    assert!(matches!(GaConfig::builder().xover_prob(1.5).build(), Err(ConfigError::OutOfRange { field: "xover_prob", .. })));
+    // Mutation gets likelier as diversity drops, it has to stay a probability
+    assert!(matches!(GaConfig::builder().p_mut_min(0.7).build(), Err(ConfigError::OutOfRange { field: "p_mut_min", .. })));
+    assert!(matches!(GaConfig::builder().migrants(20).pop_size(10).build(), Err(ConfigError::OutOfRange { field: "migrants", .. })));
+    // Both tournaments need their own individuals
+    assert!(GaConfig::builder().pop_size(4).migrants(4).build().is_ok());
+    assert!(matches!(GaConfig::builder().pop_size(3).migrants(3).build(), Err(ConfigError::OutOfRange { field: "pop_size", .. })));
+    assert!(matches!(GaConfig::builder().tournament_size(60).build(), Err(ConfigError::OutOfRange { field: "reoptimize_pop_size", .. })));
+    assert!(matches!(GaConfig::builder().continuity_cost(-1.0).build(), Err(ConfigError::OutOfRange { field: "continuity_cost", .. })));
+
+    // GenAlg keeps the population size with any offspring multiplier
+    let data = parsing::parse_json_str(&train_0_json().to_string()).unwrap();
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let fitness_function = fitness::TrainDataFitness::new(&data);
+    let population = population_init::pop_init::init_pop_random::<Route, _>(&data, 20, &fitness_function, &mut rng);
+    let mut algo = gen_alg::GenAlg::new(
+        selection::ElitismSurvivorSelection::new(),
+        selection::TournamentParentSelection::new(2),
+        crossover::OrderOneCrossover::new(),
+        fitness_function,
+        &data, mutation_vec(&mut rng, &data),
+    ).with_config(GaConfig::builder().pop_size_multiplier(1).xover_prob(1.0).build().unwrap());
+    assert_eq!(algo.evolve(&mut rng, &population).len(), 20);
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use crate::parsing::{Nurse, TrainData};
+
+/// Relative slack allowed before a shortcut through a third node counts as breaking the triangle inequality
+const TRIANGLE_TOLERANCE: f64 = 0.01;
+/// Shortcuts reported one by one, the ones saving the most travel time. The rest are only counted
+const TRIANGLE_REPORTED: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    NoNurses,
+    MissingPatient { patient: usize },
+    MatrixSize { expected: usize, rows: usize, columns: usize },
+    NegativeTravelTime { from: usize, to: usize, time: f64 },
+    EmptyWindow { patient: usize, start_time: f64, end_time: f64 },
+    CareExceedsWindow { patient: usize, care_time: f64, window: f64 },
+    DemandExceedsCapacity { patient: usize, demand: i32, capacity: i32 },
+    /// No nurse driving straight from its start location can finish care in time and get back before its shift ends
+    UnreachableWindow { patient: usize, earliest_finish: f64, end_time: f64, earliest_return: f64 },
+    /// No nurse has every skill the patient needs
+    NoQualifiedNurse { patient: usize },
+    TriangleInequality { from: usize, via: usize, to: usize, direct: f64, detour: f64 },
+    /// `pairs` travel times have a shortcut, only the worst `reported` are listed
+    TriangleInequalities { pairs: usize, reported: usize },
+}
+
+/// A single finding from [`TrainData::validate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub issue: Issue,
+}
+
+impl Diagnostic {
+    fn error(issue: Issue) -> Self {
+        Self { severity: Severity::Error, issue }
+    }
+
+    fn warning(issue: Issue) -> Self {
+        Self { severity: Severity::Warning, issue }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl TrainData {
+    /// Checks the instance for inconsistencies the parser accepts but the solver can not satisfy.
+    /// Errors make the instance unsolvable without violations, warnings are suspicious but harmless.
+    /// Issues of patients with a drop cost are warnings.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let num_patients = self.nbr_patients();
+
+        if self.nbr_nurses < 1 {
+            diagnostics.push(Diagnostic::error(Issue::NoNurses));
+        }
+
+        // Nurses may start and end at extra nodes after the patients
+        let size = if self.nurses.is_some() { self.travel_times.len().max(num_patients + 1) } else { num_patients + 1 };
+        let columns = self.travel_times.iter().map(|r| r.len()).find(|&c| c != size).unwrap_or(size);
+        let matrix_ok = self.travel_times.len() == size && columns == size;
+        if !matrix_ok {
+            diagnostics.push(Diagnostic::error(Issue::MatrixSize { expected: size, rows: self.travel_times.len(), columns }));
+        }
+
+        let nurses: Vec<&Nurse> = (0..self.nbr_nurses.max(0) as usize).map(|n| self.nurse(n)).collect();
+        let max_capacity = nurses.iter().map(|n| n.capacity).max().unwrap_or(0);
+        for patient in 1..=num_patients {
+            let p = match self.patients.get(&patient.to_string()) {
+                Some(p) => p,
+                None => {
+                    diagnostics.push(Diagnostic::error(Issue::MissingPatient { patient }));
+                    continue;
+                }
+            };
+
+            let patient_issue: fn(Issue) -> Diagnostic = if p.drop_cost.is_some() { Diagnostic::warning } else { Diagnostic::error };
+            if p.start_time > p.end_time {
+                diagnostics.push(patient_issue(Issue::EmptyWindow { patient, start_time: p.start_time, end_time: p.end_time }));
+            } else if p.has_hard_window() && p.care_time > p.end_time - p.start_time {
+                diagnostics.push(patient_issue(Issue::CareExceedsWindow { patient, care_time: p.care_time, window: p.end_time - p.start_time }));
+            }
+            if p.demand > max_capacity {
+                diagnostics.push(patient_issue(Issue::DemandExceedsCapacity { patient, demand: p.demand, capacity: max_capacity }));
+            }
+
+            if !nurses.is_empty() && self.qualifications().qualified_nurses(patient as u16).next().is_none() {
+                diagnostics.push(patient_issue(Issue::NoQualifiedNurse { patient }));
+            }
+
+            // Reachable if any nurse driving straight there finishes in time and gets back before the shift ends
+            if matrix_ok && !nurses.is_empty() {
+                let direct: Vec<(f64, f64, f64)> = nurses.iter().map(|n| {
+                    let finish = p.care_start(n.shift_start + self.travel_time(n.start_location, patient, n.shift_start)) + p.care_time;
+                    (finish, finish + self.travel_time(patient, n.end_location, finish), n.shift_end)
+                }).collect();
+                let reachable = direct.iter().any(|&(finish, back, shift_end)| (!p.has_hard_window() || finish <= p.end_time) && back <= shift_end);
+                if !reachable {
+                    let earliest_finish = direct.iter().map(|d| d.0).fold(f64::INFINITY, f64::min);
+                    let earliest_return = direct.iter().map(|d| d.1).fold(f64::INFINITY, f64::min);
+                    diagnostics.push(patient_issue(Issue::UnreachableWindow { patient, earliest_finish, end_time: p.end_time, earliest_return }));
+                }
+            }
+        }
+
+        if matrix_ok {
+            diagnostics.extend(self.matrix_diagnostics());
+        }
+        diagnostics
+    }
+
+    /// Negative entries are errors. Shortcuts through a third node are warnings, with the best detour of a pair,
+    /// the [`TRIANGLE_REPORTED`] saving the most and a count if there are more
+    fn matrix_diagnostics(&self) -> Vec<Diagnostic> {
+        let matrix = &self.travel_times;
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let mut shortcuts: Vec<Issue> = Vec::new();
+        for (from, row) in matrix.iter().enumerate() {
+            // Shortest detour to every node and the node it goes via, row by row through the matrix
+            let mut detours = vec![(f64::INFINITY, 0); row.len()];
+            for (via, leg) in row.iter().enumerate().filter(|&(via, _)| via != from) {
+                for (to, next) in matrix[via].iter().enumerate() {
+                    if leg + next < detours[to].0 && to != via {
+                        detours[to] = (leg + next, via);
+                    }
+                }
+            }
+            for (to, &direct) in row.iter().enumerate() {
+                if direct < 0.0 {
+                    diagnostics.push(Diagnostic::error(Issue::NegativeTravelTime { from, to, time: direct }));
+                    continue;
+                }
+                let (detour, via) = detours[to];
+                if from != to && direct > detour * (1.0 + TRIANGLE_TOLERANCE) {
+                    shortcuts.push(Issue::TriangleInequality { from, via, to, direct, detour });
+                }
+            }
+        }
+
+        let saving = |issue: &Issue| match issue {
+            Issue::TriangleInequality { direct, detour, .. } => direct - detour,
+            _ => 0.0,
+        };
+        shortcuts.sort_by(|a, b| saving(b).total_cmp(&saving(a)));
+        if shortcuts.len() > TRIANGLE_REPORTED {
+            diagnostics.push(Diagnostic::warning(Issue::TriangleInequalities { pairs: shortcuts.len(), reported: TRIANGLE_REPORTED }));
+        }
+        diagnostics.extend(shortcuts.into_iter().take(TRIANGLE_REPORTED).map(Diagnostic::warning));
+        diagnostics
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: ", severity)?;
+        match &self.issue {
+            Issue::NoNurses => write!(f, "instance has no nurses"),
+            Issue::MissingPatient { patient } => write!(f, "patient {} is missing", patient),
+            Issue::MatrixSize { expected, rows, columns } =>
+                write!(f, "travel_times is {}x{}, expected {}x{}", rows, columns, expected, expected),
+            Issue::NegativeTravelTime { from, to, time } =>
+                write!(f, "negative travel time {} from {} to {}", time, from, to),
+            Issue::EmptyWindow { patient, start_time, end_time } =>
+                write!(f, "patient {} has start_time {} after end_time {}", patient, start_time, end_time),
+            Issue::CareExceedsWindow { patient, care_time, window } =>
+                write!(f, "patient {} needs {} care in a window of {}", patient, care_time, window),
+            Issue::DemandExceedsCapacity { patient, demand, capacity } =>
+                write!(f, "patient {} demands {}, nurse capacity is {}", patient, demand, capacity),
+            Issue::UnreachableWindow { patient, earliest_finish, end_time, earliest_return } =>
+                write!(f, "patient {} is unreachable: care finishes at {:.2} (end_time {}), back at the earliest at {:.2}", patient, earliest_finish, end_time, earliest_return),
+            Issue::NoQualifiedNurse { patient } =>
+                write!(f, "no nurse has the skills patient {} needs", patient),
+            Issue::TriangleInequality { from, via, to, direct, detour } =>
+                write!(f, "travel {}->{} takes {:.2}, but {:.2} via {}", from, to, direct, detour, via),
+            Issue::TriangleInequalities { pairs, reported } =>
+                write!(f, "{} travel times are longer than a detour, the {} saving the most follow", pairs, reported),
+        }
+    }
+}